@@ -20,6 +20,9 @@ use std::{
 #[cfg(any(feature = "async-io", feature = "async-std"))]
 use async_io::Async;
 
+#[cfg(all(feature = "netlink", any(feature = "async-io", feature = "async-std")))]
+pub use crate::nl::AsyncCanInterface;
+
 #[cfg(all(
     feature = "smol",
     not(any(feature = "async-io", feature = "async-std"))
@@ -226,6 +226,56 @@ pub trait Frame: EmbeddedFrame {
     fn set_data(&mut self, data: &[u8]) -> Result<(), ConstructionError>;
 }
 
+// ===== FrameData trait =====
+
+/// Zero-copy access to the ID and data of a frame.
+///
+/// This is the stable integration point for signal decoders, such as those
+/// built on [can-dbc](https://crates.io/crates/can-dbc), that only need the
+/// ID and payload of a frame, and shouldn't depend on the layout of the
+/// frame types in this crate.
+///
+/// The ID is the composite SocketCAN ID word, so extended frames have the
+/// `CAN_EFF_FLAG` bit set. This is the same convention that DBC files use
+/// for extended message IDs. The data is a borrowed slice of just the
+/// valid bytes of the payload.
+pub trait FrameData {
+    /// Gets the composite CAN ID word and the payload data of the frame.
+    fn id_and_data(&self) -> (canid_t, &[u8]);
+}
+
+impl<T: Frame> FrameData for T {
+    fn id_and_data(&self) -> (canid_t, &[u8]) {
+        (self.id_word(), self.data())
+    }
+}
+
+impl FrameData for CanRawFrame {
+    fn id_and_data(&self) -> (canid_t, &[u8]) {
+        match self {
+            CanRawFrame::Classic(frame) => {
+                let len = (frame.can_dlc as usize).min(CAN_MAX_DLEN);
+                (frame.can_id, &frame.data[..len])
+            }
+            CanRawFrame::Fd(frame) => {
+                let len = (frame.len as usize).min(CANFD_MAX_DLEN);
+                (frame.can_id, &frame.data[..len])
+            }
+        }
+    }
+}
+
+impl FrameData for CanAnyFrame {
+    fn id_and_data(&self) -> (canid_t, &[u8]) {
+        match self {
+            CanAnyFrame::Normal(frame) => frame.id_and_data(),
+            CanAnyFrame::Remote(frame) => frame.id_and_data(),
+            CanAnyFrame::Error(frame) => frame.id_and_data(),
+            CanAnyFrame::Fd(frame) => frame.id_and_data(),
+        }
+    }
+}
+
 // ===== CanAnyFrame =====
 
 /// An FD socket can read a raw classic 2.0 or FD frame.
@@ -1540,4 +1590,24 @@ mod tests {
         assert!(!frame.is_error_frame());
         assert_eq!(DATA, frame.data());
     }
+
+    #[test]
+    fn test_frame_data() {
+        let frame = CanDataFrame::new(EXT_ID, DATA).unwrap();
+        let (id, data) = frame.id_and_data();
+        assert_eq!(id, id_to_raw(EXT_ID) | CAN_EFF_FLAG);
+        assert_eq!(data, DATA);
+
+        let raw = CanRawFrame::from(*frame.as_ref());
+        assert_eq!(raw.id_and_data(), (id, DATA));
+
+        let any = CanAnyFrame::from(CanFrame::from(frame));
+        assert_eq!(any.id_and_data(), (id, DATA));
+
+        let frame = CanFdFrame::new(STD_ID, DATA).unwrap();
+        assert_eq!(frame.id_and_data(), (id_to_raw(STD_ID), DATA));
+
+        let raw = CanRawFrame::from(*frame.as_ref());
+        assert_eq!(raw.id_and_data(), (id_to_raw(STD_ID), DATA));
+    }
 }
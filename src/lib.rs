@@ -82,13 +82,23 @@
 //! * **async-std** -
 //!   Include support for async/await using [async-io](https://crates.io/crates/async-io)
 //!   with a submodule aliased for [async-std](https://crates.io/crates/async-std) and examples
-//!   for that runtime.
+//!   for that runtime. With the _netlink_ feature, this also includes an
+//!   asynchronous `AsyncCanInterface` for configuring the CAN interfaces.
 //!
 //! * **smol** -
 //!   Include support for async/await using [async-io](https://crates.io/crates/async-io)
 //!   with a submodule aliased for [smol](https://crates.io/crates/smol) and examples
 //!   for that runtime.
 //!
+//! * **serde** -
+//!   Implement `Serialize` and `Deserialize` from [serde](https://crates.io/crates/serde)
+//!   for the netlink interface details and parameter types.
+//!
+//! * **experimental** -
+//!   Low-level netlink access, such as sending raw messages for a CAN
+//!   interface. These are intended for experimenting with new or
+//!   undocumented kernel features, and are not part of the stable API.
+//!
 
 // clippy: do not warn about things like "SocketCAN" inside the docs
 #![allow(clippy::doc_markdown)]
@@ -124,7 +134,7 @@ pub use addr::CanAddr;
 pub mod frame;
 pub use frame::{
     CanAnyFrame, CanDataFrame, CanErrorFrame, CanFdFrame, CanFrame, CanRawFrame, CanRemoteFrame,
-    Frame,
+    Frame, FrameData,
 };
 
 #[cfg(feature = "dump")]
@@ -137,7 +147,7 @@ pub use socket::{CanFdSocket, CanFilter, CanSocket, ShouldRetry, Socket, SocketO
 pub mod nl;
 
 #[cfg(feature = "netlink")]
-pub use nl::{CanCtrlMode, CanInterface, InterfaceCanParams};
+pub use nl::{CanCtrlMode, CanInterface, CanNlError, InterfaceCanParams};
 
 /// Optional tokio support
 #[cfg(feature = "tokio")]
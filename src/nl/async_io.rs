@@ -0,0 +1,174 @@
+// socketcan/src/nl/async_io.rs
+//
+// Asynchronous netlink control of SocketCAN interfaces using async-io.
+//
+// This file is part of the Rust 'socketcan-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+
+//! Asynchronous netlink control of CAN interfaces for `async-io`.
+//!
+//! This works with any runtime that uses _async_io_, including
+//! [async-std](https://crates.io/crates/async-std) and
+//! [smol](https://crates.io/crates/smol).
+//!
+//! The requests are built and the responses parsed by the same code as
+//! the blocking [`CanInterface`], so the two stay in lockstep. Only the
+//! wait for the kernel's response is asynchronous.
+//!
+//! The requests honor the settings of the blocking interface, such as
+//! its timeout, strict checking, and dry-run mode.
+
+use super::{CanInterface, CanNlError, InterfaceCanParams, InterfaceDetails, NlResult};
+use async_io::{Async, Timer};
+use neli::{
+    consts::{
+        nl::{NlType, NlmF},
+        rtnl::Rtm,
+    },
+    err::{NlError, WrappedError},
+    nl::Nlmsghdr,
+    rtnl::Ifinfomsg,
+    socket::NlSocketHandle,
+    types::{Buffer, RtBuffer},
+    FromBytesWithInput, ToBytes,
+};
+use std::{
+    fmt::Debug,
+    future::{self, Future},
+    io,
+    pin::{pin, Pin},
+    task::Poll,
+};
+
+/// An asynchronous SocketCAN Netlink CAN interface.
+///
+/// This is the async counterpart to [`CanInterface`], for the most common
+/// operations. The blocking interface is available through
+/// [inner](AsyncCanInterface::inner) for anything else.
+#[derive(Debug)]
+pub struct AsyncCanInterface(CanInterface);
+
+impl AsyncCanInterface {
+    /// Open a CAN interface by name.
+    pub fn open(ifname: &str) -> Result<Self, nix::Error> {
+        Ok(Self(CanInterface::open(ifname)?))
+    }
+
+    /// Open a CAN interface by index.
+    ///
+    /// As with the blocking interface, no checks are performed to
+    /// determine if the interface actually exists.
+    pub fn open_iface(if_index: u32) -> Self {
+        Self(CanInterface::open_iface(if_index))
+    }
+
+    /// Gets a reference to the blocking interface.
+    pub fn inner(&self) -> &CanInterface {
+        &self.0
+    }
+
+    /// Opens a netlink socket registered with the async reactor.
+    ///
+    /// The socket is opened with the options of the blocking interface,
+    /// like the PID binding and strict checking.
+    fn open_socket(&self) -> NlResult<Async<NlSocketHandle>> {
+        Ok(Async::new(self.0.open_socket()?)?)
+    }
+
+    /// Sends a message to the kernel and waits for the response.
+    ///
+    /// The socket is non-blocking, so its read timeout doesn't apply.
+    /// Instead, the wait is raced against a timer for the timeout of the
+    /// blocking interface.
+    async fn request<T, P, RT, RP>(&self, msg: Nlmsghdr<T, P>) -> NlResult<Option<Nlmsghdr<RT, RP>>>
+    where
+        T: NlType + Debug,
+        P: ToBytes + Debug,
+        RT: NlType + Debug,
+        RP: for<'a> FromBytesWithInput<'a, Input = usize> + Debug,
+    {
+        let mut sock = self.open_socket()?;
+
+        // Sends to the kernel don't block, so this is done directly.
+        CanInterface::send_msg(sock.get_mut(), msg)?;
+
+        let read = sock.read_with_mut(|sock| match sock.recv::<'_, RT, RP>() {
+            Ok(None) => Err(io::ErrorKind::WouldBlock.into()),
+            Ok(msg) => Ok(Ok(msg)),
+            Err(NlError::Wrapped(WrappedError::IOError(err)))
+                if err.kind() == io::ErrorKind::WouldBlock =>
+            {
+                Err(err)
+            }
+            Err(err) => Ok(Err(CanNlError::from(err))),
+        });
+
+        let timeout = match self.0.timeout() {
+            Some(timeout) => timeout,
+            None => return read.await?,
+        };
+
+        let mut read = pin!(read);
+        let mut timer = Timer::after(timeout);
+        future::poll_fn(|cx| {
+            if let Poll::Ready(res) = read.as_mut().poll(cx) {
+                return Poll::Ready(res);
+            }
+            Pin::new(&mut timer)
+                .poll(cx)
+                .map(|_| Err(io::ErrorKind::TimedOut.into()))
+        })
+        .await?
+    }
+
+    /// Sends an info message to the kernel and waits for the ACK.
+    ///
+    /// In dry-run mode, the message is recorded by the blocking interface
+    /// rather than sent.
+    async fn send_info_msg(&self, msg_type: Rtm, info: Ifinfomsg, flags: &[NlmF]) -> NlResult<()> {
+        if self.0.is_dry_run() {
+            return self.0.send_info_msg(msg_type, info, flags);
+        }
+
+        let hdr = CanInterface::info_msg_hdr(msg_type, info, flags);
+        let msg = self.request::<_, _, u16, Buffer>(hdr).await?;
+        CanInterface::check_ack(msg)
+    }
+
+    /// Bring down this interface.
+    pub async fn bring_down(&self) -> NlResult<()> {
+        self.send_info_msg(Rtm::Newlink, self.0.down_msg(), &[])
+            .await
+    }
+
+    /// Bring up this interface.
+    pub async fn bring_up(&self) -> NlResult<()> {
+        self.send_info_msg(Rtm::Newlink, self.0.up_msg(), &[]).await
+    }
+
+    /// Attempt to query detailed information on the interface.
+    pub async fn details(&self) -> NlResult<InterfaceDetails> {
+        let hdr = self.0.details_msg_hdr()?;
+        let msg = self.request::<_, _, Rtm, Ifinfomsg>(hdr).await?;
+        self.0.details_from_msg(msg)
+    }
+
+    /// Set a collection of CAN parameters on the interface.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub async fn set_can_params(&self, params: &InterfaceCanParams) -> NlResult<()> {
+        let info = self.0.info_msg(RtBuffer::try_from(params)?);
+        self.send_info_msg(Rtm::Newlink, info, &[]).await
+    }
+}
+
+impl From<CanInterface> for AsyncCanInterface {
+    fn from(iface: CanInterface) -> Self {
+        Self(iface)
+    }
+}
@@ -0,0 +1,368 @@
+// socketcan/src/nl/config.rs
+//
+// A complete configuration for a CAN interface.
+//
+// This file is part of the Rust 'socketcan-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+
+//! A complete configuration for a CAN interface.
+//!
+//! This collects the writable settings of a CAN interface so that they
+//! can be applied together, in a single netlink request.
+
+use super::{
+    CanBitTiming, CanCtrlMode, CanCtrlModes, CanInterface, InterfaceCanParams, InterfaceDetails,
+    Mtu, NlResult, SamplePoint,
+};
+use neli::{
+    consts::rtnl::{Ifla, Rtm},
+    rtnl::Rtattr,
+    types::{Buffer, RtBuffer},
+};
+
+/// The configuration of a CAN interface.
+///
+/// Any setting that is `None` is left unchanged on the interface when the
+/// configuration is applied.
+///
+/// ```no_run
+/// # use socketcan::nl::{CanCtrlMode, CanInterface, CanInterfaceConfig, Mtu, SamplePoint};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let iface = CanInterface::open("can0")?;
+/// CanInterfaceConfig::default()
+///     .bitrate(500_000)
+///     .sample_point(SamplePoint::from_per_mille(875))
+///     .ctrlmode(CanCtrlMode::Fd, true)
+///     .mtu(Mtu::Fd)
+///     .apply(&iface)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CanInterfaceConfig {
+    bit_timing: Option<CanBitTiming>,
+    data_bit_timing: Option<CanBitTiming>,
+    ctrl_mode: Option<CanCtrlModes>,
+    restart_ms: Option<u32>,
+    termination: Option<u16>,
+    mtu: Option<Mtu>,
+}
+
+impl CanInterfaceConfig {
+    /// Creates a configuration from the details of an interface.
+    ///
+    /// This captures all of the writable settings that were reported for
+    /// the interface, so that they can be replayed onto another one, such
+    /// as to clone a known-good setup across identical adapters.
+    ///
+    /// The kernel only reports the control modes that are on, so the
+    /// configuration explicitly turns all of the other known modes off.
+    ///
+    /// The kernel reports the bit timing with both the bitrate and the
+    /// register-level values filled in, but only accepts one or the other.
+    /// So only the bitrate and sample point are kept, and the kernel
+    /// recalculates the rest when the configuration is applied.
+    pub fn from_details(details: &InterfaceDetails) -> Self {
+        let ctrl_mode = details.can.ctrl_mode.map(|modes| {
+            let mut all_modes = CanCtrlModes::default();
+            for mode in CanCtrlMode::ALL {
+                all_modes.add(mode, modes.has_mode(mode));
+            }
+            all_modes
+        });
+
+        // Just the bitrate and sample point, for the kernel to calculate
+        let calc_form = |timing: CanBitTiming| CanBitTiming {
+            bitrate: timing.bitrate,
+            sample_point: timing.sample_point,
+            ..CanBitTiming::default()
+        };
+
+        Self {
+            bit_timing: details.can.bit_timing.map(calc_form),
+            data_bit_timing: details.can.data_bit_timing.map(calc_form),
+            ctrl_mode,
+            restart_ms: details.can.restart_ms,
+            termination: details.can.termination,
+            mtu: details.mtu,
+        }
+    }
+
+    /// Sets the full bit timing for the interface.
+    pub fn bit_timing(mut self, timing: CanBitTiming) -> Self {
+        self.bit_timing = Some(timing);
+        self
+    }
+
+    /// Sets the bitrate, in bits per second.
+    ///
+    /// Unless the rest of the bit timing is also set, the kernel calculates
+    /// it from the bitrate and the sample point.
+    pub fn bitrate(mut self, bitrate: u32) -> Self {
+        self.bit_timing
+            .get_or_insert_with(CanBitTiming::default)
+            .bitrate = bitrate;
+        self
+    }
+
+    /// Sets the sample point for the bitrate.
+    pub fn sample_point(mut self, sample_point: SamplePoint) -> Self {
+        self.bit_timing
+            .get_or_insert_with(CanBitTiming::default)
+            .sample_point = sample_point.into();
+        self
+    }
+
+    /// Sets the full data bit timing for an FD interface.
+    pub fn data_bit_timing(mut self, timing: CanBitTiming) -> Self {
+        self.data_bit_timing = Some(timing);
+        self
+    }
+
+    /// Sets the data bitrate for an FD interface, in bits per second.
+    pub fn data_bitrate(mut self, bitrate: u32) -> Self {
+        self.data_bit_timing
+            .get_or_insert_with(CanBitTiming::default)
+            .bitrate = bitrate;
+        self
+    }
+
+    /// Sets the sample point for the data bitrate.
+    pub fn data_sample_point(mut self, sample_point: SamplePoint) -> Self {
+        self.data_bit_timing
+            .get_or_insert_with(CanBitTiming::default)
+            .sample_point = sample_point.into();
+        self
+    }
+
+    /// Turns a control mode on or off.
+    ///
+    /// This can be called repeatedly to set several modes. Any mode that
+    /// isn't mentioned is left unchanged.
+    pub fn ctrlmode(mut self, mode: CanCtrlMode, on: bool) -> Self {
+        self.ctrl_mode
+            .get_or_insert_with(CanCtrlModes::default)
+            .add(mode, on);
+        self
+    }
+
+    /// Sets the automatic restart delay, in milliseconds.
+    ///
+    /// A value of zero disables automatic restart.
+    pub fn restart_ms(mut self, restart_ms: u32) -> Self {
+        self.restart_ms = Some(restart_ms);
+        self
+    }
+
+    /// Sets the bus termination resistance, in ohms.
+    pub fn termination(mut self, termination: u16) -> Self {
+        self.termination = Some(termination);
+        self
+    }
+
+    /// Sets the MTU of the interface.
+    ///
+    /// A hardware CAN interface only accepts the FD MTU in FD mode, so
+    /// when the configuration is applied to one, FD mode is turned on
+    /// along with the FD MTU, unless the configuration sets the mode
+    /// explicitly.
+    pub fn mtu(mut self, mtu: Mtu) -> Self {
+        self.mtu = Some(mtu);
+        self
+    }
+
+    /// Determines if any of the CAN-specific settings are set.
+    fn has_can_params(&self) -> bool {
+        self.bit_timing.is_some()
+            || self.data_bit_timing.is_some()
+            || self.ctrl_mode.is_some()
+            || self.restart_ms.is_some()
+            || self.termination.is_some()
+    }
+
+    /// Gets the link attributes to send to the kernel for the
+    /// configuration.
+    ///
+    /// The CAN link info is left out if none of the CAN settings are set,
+    /// so that a configuration with only generic link settings, like the
+    /// MTU, can be applied to any kind of interface.
+    pub(super) fn attrs(&self) -> NlResult<RtBuffer<Ifla, Buffer>> {
+        let mut attrs = if self.has_can_params() {
+            RtBuffer::try_from(&InterfaceCanParams {
+                bit_timing: self.bit_timing,
+                data_bit_timing: self.data_bit_timing,
+                ctrl_mode: self.ctrl_mode,
+                restart_ms: self.restart_ms,
+                termination: self.termination,
+                ..InterfaceCanParams::default()
+            })?
+        } else {
+            RtBuffer::new()
+        };
+        if let Some(mtu) = self.mtu {
+            let mtu = mtu as u32;
+            attrs.push(Rtattr::new(None, Ifla::Mtu, &mtu.to_ne_bytes()[..])?);
+        }
+        Ok(attrs)
+    }
+
+    /// Adds the control modes that the MTU requires on a hardware CAN
+    /// interface, if the configuration doesn't set them already.
+    ///
+    /// Virtual interfaces have no control modes, and take any MTU.
+    fn with_required_modes(self, is_virtual: bool) -> Self {
+        let requires_fd = self.mtu.is_some_and(|mtu| mtu.requires_fd_mode());
+        let sets_fd = self
+            .ctrl_mode
+            .is_some_and(|modes| modes.masked_modes().contains(&CanCtrlMode::Fd));
+
+        if requires_fd && !sets_fd && !is_virtual {
+            self.ctrlmode(CanCtrlMode::Fd, true)
+        } else {
+            self
+        }
+    }
+
+    /// Applies the configuration to the interface.
+    ///
+    /// All of the settings are sent in a single request, so either they
+    /// are all applied, or none are. The kernel applies the CAN settings
+    /// before the MTU, so a configuration can turn on FD mode and set the
+    /// FD MTU together. As with the individual settings, the interface
+    /// must be down to change most of them.
+    ///
+    /// An FD MTU also turns on FD mode, unless the interface is virtual.
+    /// In dry-run mode, the interface isn't queried, and is assumed to be
+    /// a CAN controller.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn apply(&self, iface: &CanInterface) -> NlResult<()> {
+        let is_virtual = !iface.is_dry_run() && iface.is_virtual()?;
+        let config = self.with_required_modes(is_virtual);
+        let info = iface.info_msg(config.attrs()?);
+        iface.send_info_msg(Rtm::Newlink, info, &[])
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_details() {
+        let mut details = InterfaceDetails::new(42);
+        details.mtu = Some(Mtu::Fd);
+        // As reported by the kernel for 500kbps @ 87.5% on an 80MHz clock
+        details.can.bit_timing = Some(CanBitTiming {
+            bitrate: 500_000,
+            sample_point: 875,
+            tq: 125,
+            prop_seg: 6,
+            phase_seg1: 7,
+            phase_seg2: 2,
+            sjw: 1,
+            brp: 10,
+        });
+        details.can.restart_ms = Some(100);
+        details.can.ctrl_mode = Some(CanCtrlModes::new(0, CanCtrlMode::Fd.mask()));
+
+        let config = CanInterfaceConfig::from_details(&details);
+        assert_eq!(config.restart_ms, Some(100));
+        assert_eq!(config.mtu, Some(Mtu::Fd));
+        assert_eq!(config.data_bit_timing, None);
+
+        // The bit timing that would be sent is in the form the kernel
+        // accepts, with the bitrate set and the time quantum left at zero
+        let attrs = config.attrs().unwrap();
+        let link_info = attrs
+            .iter()
+            .find(|attr| attr.rta_type == Ifla::Linkinfo)
+            .unwrap();
+        let params = InterfaceCanParams::try_from(link_info).unwrap();
+        assert_eq!(
+            params.bit_timing,
+            Some(CanBitTiming {
+                bitrate: 500_000,
+                sample_point: 875,
+                ..CanBitTiming::default()
+            })
+        );
+
+        let modes = config.ctrl_mode.unwrap();
+        assert_eq!(modes.active_modes(), vec![CanCtrlMode::Fd]);
+        assert_eq!(modes.masked_modes(), CanCtrlMode::ALL.to_vec());
+    }
+
+    #[test]
+    fn test_fluent_setters() {
+        let config = CanInterfaceConfig::default()
+            .bitrate(500_000)
+            .sample_point(SamplePoint::from_per_mille(875))
+            .data_bitrate(2_000_000)
+            .ctrlmode(CanCtrlMode::Fd, true)
+            .ctrlmode(CanCtrlMode::ListenOnly, false)
+            .restart_ms(100)
+            .mtu(Mtu::Fd);
+
+        let timing = config.bit_timing.unwrap();
+        assert_eq!(timing.bitrate, 500_000);
+        assert_eq!(timing.sample_point, 875);
+        assert_eq!(config.data_bit_timing.unwrap().bitrate, 2_000_000);
+        assert_eq!(config.restart_ms, Some(100));
+        assert_eq!(config.termination, None);
+        assert_eq!(config.mtu, Some(Mtu::Fd));
+
+        let modes = config.ctrl_mode.unwrap();
+        assert_eq!(modes.active_modes(), vec![CanCtrlMode::Fd]);
+        assert_eq!(
+            modes.masked_modes(),
+            vec![CanCtrlMode::ListenOnly, CanCtrlMode::Fd]
+        );
+    }
+
+    #[test]
+    fn test_with_required_modes() {
+        let config = CanInterfaceConfig::default().mtu(Mtu::Fd);
+        assert_eq!(config.ctrl_mode, None);
+
+        // A virtual interface needs no control modes
+        assert_eq!(config.with_required_modes(true), config);
+
+        let modes = config.with_required_modes(false).ctrl_mode.unwrap();
+        assert_eq!(modes.active_modes(), vec![CanCtrlMode::Fd]);
+
+        // An explicit FD setting is left alone
+        let config = config.ctrlmode(CanCtrlMode::Fd, false);
+        assert_eq!(config.with_required_modes(false), config);
+
+        let config = CanInterfaceConfig::default().mtu(Mtu::Standard);
+        assert_eq!(config.with_required_modes(false), config);
+    }
+
+    #[test]
+    fn test_attrs() {
+        // Only the MTU, without any CAN link info
+        let attrs = CanInterfaceConfig::default()
+            .mtu(Mtu::Standard)
+            .attrs()
+            .unwrap();
+        let types: Vec<_> = attrs.iter().map(|attr| attr.rta_type).collect();
+        assert_eq!(types, vec![Ifla::Mtu]);
+
+        let attrs = CanInterfaceConfig::default()
+            .restart_ms(100)
+            .mtu(Mtu::Standard)
+            .attrs()
+            .unwrap();
+        let types: Vec<_> = attrs.iter().map(|attr| attr.rta_type).collect();
+        assert_eq!(types, vec![Ifla::Linkinfo, Ifla::Mtu]);
+    }
+}
@@ -0,0 +1,156 @@
+// socketcan/src/nl/create.rs
+//
+// A builder for creating CAN network interfaces.
+//
+// This file is part of the Rust 'socketcan-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+
+//! A builder for creating CAN network interfaces.
+//!
+//! This allows the initial attributes of a new interface to be set in the
+//! same netlink request that creates it, rather than changing them with
+//! separate requests afterward.
+
+use super::{CanInterface, Mtu, NlResult};
+use neli::{consts::rtnl::Ifla, rtnl::Rtattr, types::Buffer};
+use std::os::unix::io::RawFd;
+
+/// A builder for creating a new network interface.
+///
+/// ```no_run
+/// # use socketcan::nl::{CreateBuilder, Mtu};
+/// # fn main() -> Result<(), socketcan::CanNlError> {
+/// let iface = CreateBuilder::new("vcan1", "vcan")
+///     .mtu(Mtu::Fd)
+///     .txqueuelen(100)
+///     .create()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CreateBuilder {
+    name: String,
+    kind: String,
+    index: Option<u32>,
+    mtu: Option<u32>,
+    txqueuelen: Option<u32>,
+    num_tx_queues: Option<u32>,
+    num_rx_queues: Option<u32>,
+    netns_fd: Option<RawFd>,
+}
+
+impl CreateBuilder {
+    /// Creates a builder for an interface with the specified name and
+    /// kind, such as "vcan" or "vxcan".
+    ///
+    /// Note that the length of the name is capped by ```libc::IFNAMSIZ```.
+    pub fn new(name: &str, kind: &str) -> Self {
+        Self {
+            name: name.into(),
+            kind: kind.into(),
+            index: None,
+            mtu: None,
+            txqueuelen: None,
+            num_tx_queues: None,
+            num_rx_queues: None,
+            netns_fd: None,
+        }
+    }
+
+    /// Sets the index for the new interface.
+    ///
+    /// If this isn't set, the kernel picks the index.
+    pub fn index(mut self, index: u32) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// Sets the MTU for the new interface.
+    pub fn mtu(self, mtu: Mtu) -> Self {
+        self.raw_mtu(mtu as u32)
+    }
+
+    /// Sets the MTU for the new interface to an arbitrary value.
+    pub fn raw_mtu(mut self, mtu: u32) -> Self {
+        self.mtu = Some(mtu);
+        self
+    }
+
+    /// Sets the length of the transmit queue for the new interface.
+    pub fn txqueuelen(mut self, txqueuelen: u32) -> Self {
+        self.txqueuelen = Some(txqueuelen);
+        self
+    }
+
+    /// Sets the number of transmit queues for the new interface.
+    ///
+    /// The number of queues can only be set when the interface is
+    /// created. If this isn't set, the kernel uses a single queue.
+    pub fn num_tx_queues(mut self, num_tx_queues: u32) -> Self {
+        self.num_tx_queues = Some(num_tx_queues);
+        self
+    }
+
+    /// Sets the number of receive queues for the new interface.
+    ///
+    /// The number of queues can only be set when the interface is
+    /// created. If this isn't set, the kernel uses a single queue.
+    pub fn num_rx_queues(mut self, num_rx_queues: u32) -> Self {
+        self.num_rx_queues = Some(num_rx_queues);
+        self
+    }
+
+    /// Sets the network namespace in which to create the interface.
+    ///
+    /// The namespace is specified by an open file descriptor that refers
+    /// to it, such as one obtained by opening a file under `/var/run/netns`
+    /// or `/proc/<pid>/ns/net`.
+    pub fn netns(mut self, netns_fd: RawFd) -> Self {
+        self.netns_fd = Some(netns_fd);
+        self
+    }
+
+    /// Gets the additional link attributes for the creation request.
+    fn attrs(&self) -> NlResult<Vec<Rtattr<Ifla, Buffer>>> {
+        let mut attrs = Vec::new();
+        if let Some(mtu) = self.mtu {
+            attrs.push(Rtattr::new(None, Ifla::Mtu, &mtu.to_ne_bytes()[..])?);
+        }
+        if let Some(txqueuelen) = self.txqueuelen {
+            attrs.push(Rtattr::new(None, Ifla::Txqlen, txqueuelen)?);
+        }
+        if let Some(num_tx_queues) = self.num_tx_queues {
+            attrs.push(Rtattr::new(None, Ifla::NumTxQueues, num_tx_queues)?);
+        }
+        if let Some(num_rx_queues) = self.num_rx_queues {
+            attrs.push(Rtattr::new(None, Ifla::NumRxQueues, num_rx_queues)?);
+        }
+        if let Some(netns_fd) = self.netns_fd {
+            attrs.push(Rtattr::new(None, Ifla::NetNsFd, netns_fd as u32)?);
+        }
+        Ok(attrs)
+    }
+
+    /// Creates the interface with all of the attributes in a single
+    /// request.
+    ///
+    /// This returns the new interface, unless it was created in another
+    /// network namespace, in which case it can not be managed from here,
+    /// and `None` is returned.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn create(&self) -> NlResult<Option<CanInterface>> {
+        let attrs = self.attrs()?;
+        if self.netns_fd.is_some() {
+            CanInterface::send_new_link(&self.name, self.index, &self.kind, attrs)?;
+            Ok(None)
+        } else {
+            CanInterface::create_with_attrs(&self.name, self.index, &self.kind, attrs).map(Some)
+        }
+    }
+}
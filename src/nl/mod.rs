@@ -52,6 +52,7 @@
 //! <https://github.com/lalten/libsocketcan>
 //!
 
+use crate::{CanFrame, CanSocket, EmbeddedFrame, Socket, SocketOptions, StandardId};
 use neli::{
     attr::Attribute,
     consts::{
@@ -60,32 +61,297 @@ use neli::{
         rtnl::{Iff, IffFlags, Ifla, IflaInfo},
         socket::NlFamily,
     },
-    err::NlError,
+    err::{DeError, NlError, SerError, WrappedError},
     nl::{NlPayload, Nlmsghdr},
     rtnl::{Ifinfomsg, Rtattr},
     socket::NlSocketHandle,
     types::{Buffer, RtBuffer},
-    FromBytes, ToBytes,
+    FromBytes, FromBytesWithInput, ToBytes,
 };
 use nix::{self, net::if_::if_nametoindex, unistd};
-use rt::IflaCan;
+use rt::{IflaCan, VxcanInfo};
+use socket2::SockRef;
 use std::{
-    ffi::CStr,
-    fmt::Debug,
-    os::raw::{c_int, c_uint},
+    borrow::Cow,
+    ffi::{CStr, OsStr, OsString},
+    fmt::{self, Debug},
+    hash::{Hash, Hasher},
+    io::{self, Cursor},
+    mem::{self, size_of},
+    os::{
+        raw::{c_char, c_int, c_uint},
+        unix::{
+            ffi::OsStrExt,
+            io::{AsRawFd, BorrowedFd, RawFd},
+        },
+    },
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
 };
+use thiserror::Error;
 
 /// Low-level Netlink CAN struct bindings.
 mod rt;
 
 use rt::can_ctrlmode;
 pub use rt::CanState;
+pub use rt::{
+    EXT_FILTER_BRVLAN, EXT_FILTER_BRVLAN_COMPRESSED, EXT_FILTER_SKIP_STATS, EXT_FILTER_VF,
+};
+
+/// Higher-level bit timing types.
+mod timing;
+
+pub use timing::{
+    bit_time_ns, calc_bit_timing, BitTiming, BitTimingBuilder, BitTimingLimits, SamplePoint,
+};
+
+/// Complete interface configurations.
+mod config;
+
+pub use config::CanInterfaceConfig;
+
+/// Builder for creating interfaces.
+mod create;
+
+pub use create::CreateBuilder;
+
+/// Interface traffic statistics.
+mod stats;
+
+pub use stats::{StatsDelta, StatsSnapshot};
+
+/// Asynchronous interface control for `async-io` based runtimes.
+#[cfg(any(feature = "async-io", feature = "async-std"))]
+mod async_io;
+
+#[cfg(any(feature = "async-io", feature = "async-std"))]
+pub use self::async_io::AsyncCanInterface;
 
 /// A result for Netlink errors.
-type NlResult<T> = Result<T, NlError>;
+type NlResult<T> = Result<T, CanNlError>;
+
+/// The default time to wait for the kernel to respond to a netlink request.
+pub const DEFAULT_NL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The number of times a netlink send or receive that was interrupted by
+/// a signal (`EINTR`) is retried before giving up.
+const MAX_EINTR_RETRIES: usize = 8;
+
+/// An error from a netlink operation on a CAN interface.
+#[derive(Error, Debug)]
+pub enum CanNlError {
+    /// The kernel rejected the request.
+    ///
+    /// Common values are `EBUSY`, `EINVAL`, and `ENODEV`, which can be
+    /// compared against the constants in the `libc` crate.
+    #[error("{}", io::Error::from_raw_os_error(*errno))]
+    Kernel {
+        /// The (positive) error number reported by the kernel
+        errno: i32,
+    },
+    /// The kernel did not respond to the request in time.
+    #[error("timed out waiting for a netlink response")]
+    Timeout,
+    /// No message was received from the kernel.
+    ///
+    /// The socket was read without error, but there was nothing on it.
+    /// This might be a transient condition, and the request can be
+    /// retried.
+    #[error("no netlink message was received")]
+    NoMessage,
+    /// The kernel responded with something other than an acknowledgement.
+    ///
+    /// This is a protocol error, and retrying the request is unlikely to
+    /// help.
+    #[error("netlink response was not an acknowledgement")]
+    NoAck,
+    /// The interface doesn't support the parameter.
+    ///
+    /// This is reported when the kernel rejects a CAN parameter as not
+    /// supported (`EOPNOTSUPP`), such as when trying to set the FD data
+    /// bitrate on a classic CAN controller.
+    #[error("the interface doesn't support setting the {param}")]
+    NotSupported {
+        /// The name of the parameter
+        param: &'static str,
+    },
+    /// The kernel responded about a different interface than the one that
+    /// was requested.
+    ///
+    /// This can happen if the interface was deleted and another device
+    /// took its index between requests.
+    #[error("expected interface index {expected}, but the kernel reported {actual}")]
+    IndexMismatch {
+        /// The index of the interface that was requested
+        expected: u32,
+        /// The index reported by the kernel
+        actual: u32,
+    },
+    /// The request needs privileges that the process doesn't have.
+    ///
+    /// Changing an interface requires root, or the `CAP_NET_ADMIN`
+    /// capability. The kernel reports this as `EPERM`.
+    #[error("operation not permitted; it requires root or the CAP_NET_ADMIN capability")]
+    PermissionDenied,
+    /// A manual restart was requested, but the interface isn't bus-off.
+    ///
+    /// The kernel reports this as `EBUSY`.
+    #[error("the interface can't be restarted since it is not bus-off")]
+    NotBusOff,
+    /// A manual restart was requested, but automatic restart is enabled.
+    ///
+    /// The kernel reports this as `EINVAL`.
+    #[error("the interface can't be restarted manually while automatic restart is enabled")]
+    AutoRestartEnabled,
+    /// The interface was brought down for reconfiguration, but the kernel
+    /// still reports it as up.
+    #[error("the interface is still up after bringing it down")]
+    StillUp,
+    /// The interface doesn't report a CAN clock, so the bit timing can't
+    /// be worked out for it.
+    #[error("the interface has no CAN clock")]
+    NoClock,
+    /// The bit timing is outside the limits that the interface's hardware
+    /// supports.
+    #[error("the bit timing is outside the limits of the interface")]
+    TimingOutOfRange,
+    /// A request failed, and putting the interface back the way it was
+    /// also failed.
+    ///
+    /// The interface might be left in an intermediate state, such as down
+    /// or with only some of the settings changed.
+    #[error("{error}; restoring the prior state also failed: {restore_error}")]
+    RestoreFailed {
+        /// The error from the original request
+        error: Box<CanNlError>,
+        /// The error from trying to restore the prior state
+        restore_error: Box<CanNlError>,
+    },
+    /// An I/O error on the netlink socket
+    #[error(transparent)]
+    Io(io::Error),
+    /// Any other netlink error
+    #[error("{0}")]
+    Netlink(NlError),
+}
+
+impl CanNlError {
+    /// Gets the error number, if the kernel rejected the request.
+    pub fn errno(&self) -> Option<i32> {
+        match self {
+            Self::Kernel { errno } => Some(*errno),
+            Self::NotSupported { .. } => Some(libc::EOPNOTSUPP),
+            Self::PermissionDenied => Some(libc::EPERM),
+            Self::NotBusOff => Some(libc::EBUSY),
+            Self::AutoRestartEnabled => Some(libc::EINVAL),
+            Self::RestoreFailed { error, .. } => error.errno(),
+            Self::Io(err) => err.raw_os_error(),
+            _ => None,
+        }
+    }
+
+    /// Creates an error from the (positive) error number that the kernel
+    /// reported for a request.
+    fn from_errno(errno: i32) -> Self {
+        match errno {
+            libc::EPERM => Self::PermissionDenied,
+            errno => Self::Kernel { errno },
+        }
+    }
+
+    /// Whether this is a transient error, such that the request might
+    /// succeed if it is retried.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Timeout | Self::NoMessage)
+    }
+}
+
+impl<T, P> From<NlError<T, P>> for CanNlError
+where
+    T: Debug,
+    P: Debug,
+{
+    fn from(err: NlError<T, P>) -> Self {
+        match err {
+            // The kernel reports a negated errno in the error message
+            NlError::Nlmsgerr(err) => Self::from_errno(err.error.abs()),
+            NlError::Msg(msg) => Self::Netlink(NlError::Msg(msg)),
+            NlError::Ser(err) => Self::Netlink(NlError::Ser(err)),
+            NlError::De(err) => Self::Netlink(NlError::De(err)),
+            NlError::NoAck => Self::NoAck,
+            NlError::Wrapped(WrappedError::IOError(err)) => Self::from(err),
+            err => Self::Netlink(NlError::Msg(format!("{:?}", err))),
+        }
+    }
+}
+
+impl From<io::Error> for CanNlError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            // A receive timeout on the socket is reported as EAGAIN
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => Self::Timeout,
+            _ => Self::Io(err),
+        }
+    }
+}
+
+impl From<SerError> for CanNlError {
+    fn from(err: SerError) -> Self {
+        Self::Netlink(NlError::Ser(err))
+    }
+}
+
+impl From<DeError> for CanNlError {
+    fn from(err: DeError) -> Self {
+        Self::Netlink(NlError::De(err))
+    }
+}
+
+/// The operational state of an interface, as defined in RFC 2863.
+///
+/// This is distinct from the administrative up/down state of the
+/// interface. An interface can be administratively up, but operationally
+/// down, such as when it has no carrier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OperState {
+    /// The state is unknown. This is typical of virtual interfaces.
+    Unknown = 0,
+    /// Some component of the interface is missing
+    NotPresent = 1,
+    /// The interface is down
+    Down = 2,
+    /// The interface is down due to the state of a lower-layer interface
+    LowerLayerDown = 3,
+    /// The interface is in a test mode
+    Testing = 4,
+    /// The interface is waiting for an external event
+    Dormant = 5,
+    /// The interface is up and able to pass packets
+    Up = 6,
+}
+
+impl TryFrom<u8> for OperState {
+    type Error = io::Error;
 
-/// A Netlink error from an info query
-type NlInfoError = NlError<Rtm, Ifinfomsg>;
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
+        use OperState::*;
+
+        match val {
+            0 => Ok(Unknown),
+            1 => Ok(NotPresent),
+            2 => Ok(Down),
+            3 => Ok(LowerLayerDown),
+            4 => Ok(Testing),
+            5 => Ok(Dormant),
+            6 => Ok(Up),
+            _ => Err(io::Error::from(io::ErrorKind::InvalidData)),
+        }
+    }
+}
 
 /// CAN bit-timing parameters
 pub type CanBitTiming = rt::can_bittiming;
@@ -98,17 +364,36 @@ pub type CanBerrCounter = rt::can_berr_counter;
 
 /// The details of the interface which can be obtained with the
 /// `CanInterface::details()` function.
+///
+/// Two sets of details are only equal if they are for the same interface
+/// index, as well as having all the same parameters.
 #[allow(missing_copy_implementations)]
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InterfaceDetails {
     /// The name of the interface
-    pub name: Option<String>,
+    ///
+    /// Interface names are not guaranteed to be valid UTF-8, so this is
+    /// kept as an OS string. See [name_lossy](InterfaceDetails::name_lossy)
+    /// for a displayable version.
+    pub name: Option<OsString>,
     /// The index of the interface
     pub index: c_uint,
     /// Whether the interface is currently up
     pub is_up: bool,
+    /// The ARP hardware type of the interface (`ARPHRD_*`).
+    /// This is `ARPHRD_CAN` (280) for CAN interfaces.
+    pub arp_type: u16,
     /// The MTU size of the interface (Standard or FD frames support)
     pub mtu: Option<Mtu>,
+    /// The index of the master device, such as a bridge, that the
+    /// interface is enslaved to. This is `None` for a standalone interface.
+    pub master: Option<u32>,
+    /// The kind of link, such as "can", "vcan", or "vxcan"
+    pub kind: Option<String>,
+    /// The kind of the master link, such as "bond", if the interface is
+    /// enslaved to one
+    pub slave_kind: Option<String>,
     /// The CAN-specific parameters for the interface
     pub can: InterfaceCanParams,
 }
@@ -121,12 +406,85 @@ impl InterfaceDetails {
             ..Self::default()
         }
     }
+
+    /// Gets the name of the interface as a string, for display.
+    ///
+    /// Any bytes in the name that are not valid UTF-8 are replaced with
+    /// the Unicode replacement character.
+    pub fn name_lossy(&self) -> Option<Cow<'_, str>> {
+        self.name.as_deref().map(OsStr::to_string_lossy)
+    }
+
+    /// Queries the kernel for the details of the interface with the
+    /// specified name.
+    ///
+    /// This resolves the name and fetches the details in one step. If no
+    /// interface has the name, it fails with an `ENODEV` I/O error.
+    pub fn query(ifname: &str) -> NlResult<Self> {
+        CanInterface::open_with_details(ifname).map(|(_, details)| details)
+    }
+
+    /// Parses the interface details out of a link message that has
+    /// already been received, such as one from an application's own
+    /// netlink monitor or dump loop, without querying the kernel again.
+    pub fn from_nlmsg(msg: &Nlmsghdr<Rtm, Ifinfomsg>) -> NlResult<Self> {
+        let payload = msg.get_payload().map_err(|_| {
+            CanNlError::Netlink(NlError::Msg("The link message has no payload".into()))
+        })?;
+        Self::try_from(payload)
+    }
+}
+
+impl TryFrom<&Ifinfomsg> for InterfaceDetails {
+    type Error = CanNlError;
+
+    /// Try to parse the interface details out of a link info message
+    fn try_from(payload: &Ifinfomsg) -> Result<Self, Self::Error> {
+        let mut info = Self::new(payload.ifi_index as c_uint);
+        info.is_up = payload.ifi_flags.contains(&Iff::Up);
+        info.arp_type = u16::from(payload.ifi_type);
+
+        for attr in payload.rtattrs.iter() {
+            match attr.rta_type {
+                Ifla::Ifname => {
+                    info.name = Some(os_string_from_nul_bytes(attr.rta_payload.as_ref()));
+                }
+                Ifla::Mtu => {
+                    info.mtu = attr
+                        .get_payload_as::<u32>()
+                        .ok()
+                        .and_then(|mtu| Mtu::try_from(mtu).ok());
+                }
+                Ifla::Master => {
+                    info.master = attr.get_payload_as::<u32>().ok();
+                }
+                Ifla::Linkinfo => {
+                    for link_info in attr.get_attr_handle::<IflaInfo>()?.get_attrs() {
+                        let kind = || {
+                            os_string_from_nul_bytes(link_info.rta_payload.as_ref())
+                                .to_string_lossy()
+                                .into_owned()
+                        };
+                        match link_info.rta_type {
+                            IflaInfo::Kind => info.kind = Some(kind()),
+                            IflaInfo::SlaveKind => info.slave_kind = Some(kind()),
+                            _ => (),
+                        }
+                    }
+                    info.can = InterfaceCanParams::try_from(attr)?;
+                }
+                _ => (),
+            }
+        }
+        Ok(info)
+    }
 }
 
 /// The MTU size for the interface
 ///
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u32)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Mtu {
     /// Standard CAN frame, 8-byte data (16-byte total)
     Standard = 16,
@@ -134,21 +492,32 @@ pub enum Mtu {
     Fd = 72,
 }
 
+impl Mtu {
+    /// Determines if the interface must be in FD mode to use this MTU.
+    ///
+    /// The kernel rejects the FD MTU unless the FD control mode is on, so
+    /// the two must be set together.
+    pub fn requires_fd_mode(&self) -> bool {
+        *self == Mtu::Fd
+    }
+}
+
 impl TryFrom<u32> for Mtu {
-    type Error = std::io::Error;
+    type Error = io::Error;
 
     fn try_from(val: u32) -> Result<Self, Self::Error> {
         match val {
             16 => Ok(Mtu::Standard),
             72 => Ok(Mtu::Fd),
-            _ => Err(std::io::Error::from(std::io::ErrorKind::InvalidData)),
+            _ => Err(io::Error::from(io::ErrorKind::InvalidData)),
         }
     }
 }
 
 /// The CAN-specific parameters for the interface.
 #[allow(missing_copy_implementations)]
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InterfaceCanParams {
     /// The CAN bit timing parameters
     pub bit_timing: Option<CanBitTiming>,
@@ -171,10 +540,12 @@ pub struct InterfaceCanParams {
     pub data_bit_timing_const: Option<CanBitTimingConst>,
     /// The CANbus termination resistance
     pub termination: Option<u16>,
+    /// The maximum bitrate supported by the transceiver (read-only)
+    pub bitrate_max: Option<u32>,
 }
 
 impl TryFrom<&Rtattr<Ifla, Buffer>> for InterfaceCanParams {
-    type Error = NlInfoError;
+    type Error = CanNlError;
 
     /// Try to parse the CAN parameters out of a Linkinfo attribute
     fn try_from(link_info: &Rtattr<Ifla, Buffer>) -> Result<Self, Self::Error> {
@@ -217,6 +588,9 @@ impl TryFrom<&Rtattr<Ifla, Buffer>> for InterfaceCanParams {
                         IflaCan::Termination => {
                             params.termination = Some(attr.get_payload_as::<u16>()?);
                         }
+                        IflaCan::BitRateMax => {
+                            params.bitrate_max = Some(attr.get_payload_as::<u32>()?);
+                        }
                         _ => (),
                     }
                 }
@@ -227,7 +601,7 @@ impl TryFrom<&Rtattr<Ifla, Buffer>> for InterfaceCanParams {
 }
 
 impl TryFrom<&InterfaceCanParams> for RtBuffer<Ifla, Buffer> {
-    type Error = NlError;
+    type Error = CanNlError;
 
     /// Try to parse the CAN parameters into a NetLink buffer
     fn try_from(params: &InterfaceCanParams) -> Result<Self, Self::Error> {
@@ -260,7 +634,7 @@ impl TryFrom<&InterfaceCanParams> for RtBuffer<Ifla, Buffer> {
         }
 
         let mut link_info = Rtattr::new(None, Ifla::Linkinfo, Buffer::new())?;
-        link_info.add_nested_attribute(&Rtattr::new(None, IflaInfo::Kind, "can")?)?;
+        link_info.add_nested_attribute(&Rtattr::new(None, IflaInfo::Kind, KIND_CAN)?)?;
         link_info.add_nested_attribute(&data)?;
 
         rtattrs.push(link_info);
@@ -276,36 +650,64 @@ impl TryFrom<&InterfaceCanParams> for RtBuffer<Ifla, Buffer> {
 /// Note that these correspond to the bit _numbers_ for the control mode bits.
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CanCtrlMode {
-    /// Loopback mode
+    /// Loopback mode (Linux 2.6.31)
     Loopback,
-    /// Listen-only mode
+    /// Listen-only mode (Linux 2.6.31)
     ListenOnly,
-    /// Triple sampling mode
+    /// Triple sampling mode (Linux 2.6.31)
     TripleSampling,
-    /// One-Shot mode
+    /// One-Shot mode (Linux 2.6.35)
     OneShot,
-    /// Bus-error reporting
+    /// Bus-error reporting (Linux 2.6.35)
     BerrReporting,
-    /// CAN FD mode
+    /// CAN FD mode (Linux 3.6)
     Fd,
-    /// Ignore missing CAN ACKs
+    /// Ignore missing CAN ACKs (Linux 4.1)
     PresumeAck,
-    /// CAN FD in non-ISO mode
+    /// CAN FD in non-ISO mode (Linux 4.1)
     NonIso,
-    /// Classic CAN DLC option
+    /// Classic CAN DLC option (Linux 5.11)
     CcLen8Dlc,
 }
 
 impl CanCtrlMode {
+    /// All of the known control modes, in bit order.
+    pub const ALL: [CanCtrlMode; 9] = [
+        CanCtrlMode::Loopback,
+        CanCtrlMode::ListenOnly,
+        CanCtrlMode::TripleSampling,
+        CanCtrlMode::OneShot,
+        CanCtrlMode::BerrReporting,
+        CanCtrlMode::Fd,
+        CanCtrlMode::PresumeAck,
+        CanCtrlMode::NonIso,
+        CanCtrlMode::CcLen8Dlc,
+    ];
+
     /// Get the mask for the specific control mode
+    ///
+    /// This is the bit for the mode in the 32-bit `can_ctrlmode` struct
+    /// that is exchanged with the kernel.
     pub fn mask(&self) -> u32 {
         1u32 << (*self as u32)
     }
+
+    /// Get the mask for the specific control mode, as a 64-bit value.
+    ///
+    /// The kernel's control modes all currently fit into 32 bits, so this
+    /// is the same as [mask](CanCtrlMode::mask), but widened. It leaves
+    /// room for newer modes, like those for CAN XL, should they ever
+    /// outgrow the 32-bit field.
+    pub fn mask64(&self) -> u64 {
+        1u64 << (*self as u32)
+    }
 }
 
 /// The collection of control modes
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CanCtrlModes(can_ctrlmode);
 
 impl CanCtrlModes {
@@ -327,15 +729,61 @@ impl CanCtrlModes {
         self.0.mask |= mask;
         if on {
             self.0.flags |= mask;
+        } else {
+            self.0.flags &= !mask;
         }
     }
 
+    /// Determines if the specified mode is turned on.
+    ///
+    /// Note that when the modes are read back from the kernel, only the
+    /// flags are reported; the mask is zero.
+    pub fn has_mode(&self, mode: CanCtrlMode) -> bool {
+        (self.0.flags & mode.mask()) != 0
+    }
+
+    /// Gets the modes in the collection that are turned on.
+    ///
+    /// As with [has_mode](CanCtrlModes::has_mode), this only considers the
+    /// flags, so that it works for modes read back from the kernel.
+    pub fn active_modes(&self) -> Vec<CanCtrlMode> {
+        CanCtrlMode::ALL
+            .into_iter()
+            .filter(|mode| self.has_mode(*mode))
+            .collect()
+    }
+
+    /// Gets the modes in the collection that are being configured.
+    ///
+    /// These are the modes with their bit set in the mask, whether they
+    /// are being turned on or off. Any other modes would be left
+    /// unchanged when the collection is sent to the kernel.
+    pub fn masked_modes(&self) -> Vec<CanCtrlMode> {
+        CanCtrlMode::ALL
+            .into_iter()
+            .filter(|mode| (self.0.mask & mode.mask()) != 0)
+            .collect()
+    }
+
     /// Clears all of the mode flags in the collection
     pub fn clear(&mut self) {
         self.0 = can_ctrlmode::default();
     }
 }
 
+impl FromIterator<(CanCtrlMode, bool)> for CanCtrlModes {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (CanCtrlMode, bool)>,
+    {
+        let mut modes = Self::default();
+        for (mode, on) in iter {
+            modes.add(mode, on);
+        }
+        modes
+    }
+}
+
 impl From<can_ctrlmode> for CanCtrlModes {
     fn from(mode: can_ctrlmode) -> Self {
         Self(mode)
@@ -350,6 +798,86 @@ impl From<CanCtrlModes> for can_ctrlmode {
 
 // ===== CanInterface =====
 
+/// Gets a human-readable name for a CAN parameter, for error messages.
+fn can_param_name(param: &IflaCan) -> &'static str {
+    match param {
+        IflaCan::BitTiming => "bit timing",
+        IflaCan::CtrlMode => "control mode",
+        IflaCan::RestartMs => "restart time",
+        IflaCan::Restart => "restart",
+        IflaCan::DataBitTiming => "FD data bit timing",
+        IflaCan::Termination => "termination",
+        _ => "CAN parameter",
+    }
+}
+
+/// Converts the raw bytes of a string attribute, such as an interface
+/// name, link kind, or alias, into an OS string.
+///
+/// The kernel normally sends a nul-terminated string, but this doesn't
+/// rely on it: the string is trimmed at the first nul, if any. Interface
+/// names are arbitrary bytes, so they are kept as-is, even if they are
+/// not valid UTF-8.
+fn os_string_from_nul_bytes(bytes: &[u8]) -> OsString {
+    let s = bytes.split(|&b| b == 0).next().unwrap_or_default();
+    OsStr::from_bytes(s).to_os_string()
+}
+
+/// Gets the netlink port that a socket is bound to.
+fn socket_port(sock: &NlSocketHandle) -> io::Result<u32> {
+    let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+    let mut len = size_of::<libc::sockaddr_nl>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockname(
+            sock.as_raw_fd(),
+            &mut addr as *mut _ as *mut libc::sockaddr,
+            &mut len,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(addr.nl_pid)
+}
+
+/// Repeatedly calls `f` until it returns a value or the `timeout`
+/// expires, backing off between calls.
+///
+/// This returns `None` if the timeout expired first.
+fn poll_until<T, F>(timeout: Duration, mut f: F) -> NlResult<Option<T>>
+where
+    F: FnMut() -> NlResult<Option<T>>,
+{
+    const MIN_DELAY: Duration = Duration::from_millis(10);
+    const MAX_DELAY: Duration = Duration::from_millis(250);
+
+    let deadline = Instant::now() + timeout;
+    let mut delay = MIN_DELAY;
+
+    loop {
+        if let Some(val) = f()? {
+            return Ok(Some(val));
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(delay.min(deadline - now));
+        delay = (2 * delay).min(MAX_DELAY);
+    }
+}
+
+/// The link kind of a CAN controller, as registered by the CAN device
+/// driver framework.
+const KIND_CAN: &str = "can";
+
+/// The link kind of a virtual CAN interface.
+const KIND_VCAN: &str = "vcan";
+
+/// The link kind of a virtual CAN tunnel, which is one of a pair of
+/// connected interfaces.
+const KIND_VXCAN: &str = "vxcan";
+
 /// SocketCAN Netlink CanInterface
 ///
 /// Controlled through the kernel's Netlink interface, CAN devices can be
@@ -363,13 +891,74 @@ impl From<CanCtrlModes> for can_ctrlmode {
 /// Some actions possible on this interface require the process/user to have
 /// the `CAP_NET_ADMIN` capability, like the root user does. This is
 /// indicated by their documentation starting with "PRIVILEGED:".
+///
+/// Two interface objects are equal, and hash the same, if they have the
+/// same interface index, regardless of their other settings. So they can
+/// be used as keys in a map or set. But as noted above, this doesn't
+/// guarantee that the index still refers to the same device.
 #[allow(missing_copy_implementations)]
-#[derive(Debug)]
 pub struct CanInterface {
     if_index: c_uint,
+    timeout: Option<Duration>,
+    ext_mask: Option<c_uint>,
+    cache_socket: bool,
+    strict_check: bool,
+    bind_pid: bool,
+    // These are boxed to keep the interface small, since it's returned
+    // in errors
+    sock: Mutex<Option<Box<NlSocketHandle>>>,
+    dry_run: Option<Box<DryRunMsgs>>,
+}
+
+/// The messages that were built, but not sent, in dry-run mode.
+type DryRunMsgs = Mutex<Vec<Nlmsghdr<Rtm, Ifinfomsg>>>;
+
+impl PartialEq for CanInterface {
+    fn eq(&self, other: &Self) -> bool {
+        self.if_index == other.if_index
+    }
+}
+
+impl Eq for CanInterface {}
+
+impl Hash for CanInterface {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.if_index.hash(state);
+    }
+}
+
+impl Debug for CanInterface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let has_sock = self.sock.lock().map(|sock| sock.is_some()).ok();
+        f.debug_struct("CanInterface")
+            .field("if_index", &self.if_index)
+            .field("timeout", &self.timeout)
+            .field("ext_mask", &self.ext_mask)
+            .field("cache_socket", &self.cache_socket)
+            .field("strict_check", &self.strict_check)
+            .field("bind_pid", &self.bind_pid)
+            .field("has_socket", &has_sock)
+            .field("dry_run", &self.dry_run.is_some())
+            .finish()
+    }
 }
 
 impl CanInterface {
+    /// The fastest bitrate for classic CAN, in bits per second, as set by
+    /// the CAN 2.0 specification.
+    ///
+    /// Some controllers and transceivers can run classic CAN faster than
+    /// this, so prefer [bitrate_max](CanInterface::bitrate_max), which
+    /// uses the limit reported by the hardware, when there is one.
+    pub const MAX_CLASSIC_BITRATE: u32 = 1_000_000;
+
+    /// The slowest bitrate that can be requested, in bits per second.
+    ///
+    /// This is only the lower bound of a valid request. The limits of the
+    /// hardware are given by
+    /// [supported_bitrate_range](CanInterface::supported_bitrate_range).
+    pub const MIN_BITRATE: u32 = 1;
+
     /// Open a CAN interface by name.
     ///
     /// Similar to `open_iface`, but looks up the device by name instead of
@@ -379,6 +968,35 @@ impl CanInterface {
         Ok(Self::open_iface(if_index))
     }
 
+    /// Waits for an interface with the specified name to appear, and then
+    /// opens it.
+    ///
+    /// This is for hot-plugged devices, like USB CAN adapters, that might
+    /// not exist yet when the application starts. It polls for the
+    /// interface, backing off between checks, until it appears or the
+    /// `timeout` expires, in which case it fails with
+    /// [`CanNlError::Timeout`].
+    pub fn wait_for(ifname: &str, timeout: Duration) -> NlResult<Self> {
+        let if_index = poll_until(timeout, || match if_nametoindex(ifname) {
+            Ok(if_index) => Ok(Some(if_index)),
+            Err(nix::Error::ENODEV) => Ok(None),
+            Err(err) => Err(io::Error::from(err).into()),
+        })?;
+        if_index.map(Self::open_iface).ok_or(CanNlError::Timeout)
+    }
+
+    /// Open a CAN interface by name, and get its details.
+    ///
+    /// This is the same as calling [open](CanInterface::open) followed by
+    /// [details](CanInterface::details), and is a convenient way to both
+    /// open the interface and confirm that it exists.
+    pub fn open_with_details(ifname: &str) -> NlResult<(Self, InterfaceDetails)> {
+        let if_index = if_nametoindex(ifname).map_err(io::Error::from)?;
+        let iface = Self::open_iface(if_index);
+        let details = iface.details()?;
+        Ok((iface, details))
+    }
+
     /// Open a CAN interface.
     ///
     /// Creates a new `CanInterface` instance.
@@ -388,7 +1006,230 @@ impl CanInterface {
     /// the specified index actually exists.
     pub fn open_iface(if_index: u32) -> Self {
         let if_index = if_index as c_uint;
-        Self { if_index }
+        Self {
+            if_index,
+            timeout: Some(DEFAULT_NL_TIMEOUT),
+            ext_mask: Some(EXT_FILTER_VF),
+            cache_socket: false,
+            strict_check: false,
+            bind_pid: true,
+            sock: Mutex::new(None),
+            dry_run: None,
+        }
+    }
+
+    /// Gets the index of the interface.
+    ///
+    /// This is what's needed to bind a CAN socket to the interface, for
+    /// example, after opening it by name.
+    pub fn index(&self) -> u32 {
+        self.if_index
+    }
+
+    /// Open a CAN interface by index in dry-run mode.
+    ///
+    /// In dry-run mode, any request that would change the interface, such
+    /// as bringing it up or setting the bitrate, is built and serialized
+    /// to check that it is valid, but it is never sent to the kernel.
+    /// Instead it is kept, to be inspected with
+    /// [take_dry_run_msgs](CanInterface::take_dry_run_msgs), and the
+    /// request returns `Ok(())`. This allows the configuration logic of an
+    /// application to be tested without root privilege.
+    ///
+    /// Queries, like [details](CanInterface::details), are not affected,
+    /// and still go to the kernel. The operations that combine several
+    /// requests, like [reconfigure](CanInterface::reconfigure) and
+    /// [apply_config_with_restart](CanInterface::apply_config_with_restart),
+    /// skip the queries that they would otherwise make along the way, so
+    /// that they run without any access to the kernel.
+    pub fn dry_run(if_index: u32) -> Self {
+        Self {
+            dry_run: Some(Box::new(Mutex::new(Vec::new()))),
+            ..Self::open_iface(if_index)
+        }
+    }
+
+    /// Determines if the interface is in dry-run mode.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.is_some()
+    }
+
+    /// Takes the messages that were built, but not sent, in dry-run mode.
+    ///
+    /// The messages are returned in the order in which they were built,
+    /// and are removed from the interface. This is always empty when the
+    /// interface is not in dry-run mode.
+    pub fn take_dry_run_msgs(&self) -> Vec<Nlmsghdr<Rtm, Ifinfomsg>> {
+        match &self.dry_run {
+            Some(msgs) => msgs
+                .lock()
+                .map(|mut msgs| mem::take(&mut *msgs))
+                .unwrap_or_default(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Uses an existing netlink socket for all requests on the interface.
+    ///
+    /// This is useful when the application already manages a route socket
+    /// and wants to share it, rather than have the interface open its own.
+    /// This turns on socket caching, with the specified socket as the
+    /// cached one. The socket's read timeout is left as it was configured
+    /// by the caller.
+    pub fn with_socket(mut self, sock: NlSocketHandle) -> Self {
+        self.cache_socket = true;
+        self.sock = Mutex::new(Some(Box::new(sock)));
+        self
+    }
+
+    /// Whether a netlink socket is kept open and reused between requests.
+    pub fn cache_socket(&self) -> bool {
+        self.cache_socket
+    }
+
+    /// Sets whether to keep a netlink socket open between requests.
+    ///
+    /// By default a new socket is opened and bound for each request to the
+    /// kernel. That's fine for occasional configuration, but in a tight
+    /// polling loop the extra system calls add up. When caching is on, the
+    /// socket is opened on first use and reused after that. If a request
+    /// fails, the cached socket is dropped, and a new one is opened for
+    /// the next request, since the old one might hold a stale response.
+    ///
+    /// Turning caching off closes any cached socket.
+    pub fn set_cache_socket(&mut self, on: bool) {
+        self.cache_socket = on;
+        if !on {
+            if let Ok(sock) = self.sock.get_mut() {
+                *sock = None;
+            }
+        }
+    }
+
+    /// Gets the netlink port that the cached socket is bound to.
+    ///
+    /// This is normally the PID of the process, unless that was already
+    /// taken by another socket, in which case it's a unique port assigned
+    /// by the kernel. It is `None` if there is no cached socket. This is
+    /// mainly useful for diagnostics, such as matching the socket to the
+    /// output of `ss -f netlink`.
+    pub fn netlink_port(&self) -> NlResult<Option<u32>> {
+        match self.sock.lock().as_deref() {
+            Ok(Some(sock)) => Ok(Some(socket_port(sock)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Gets the extended filter mask sent with queries to the kernel.
+    pub fn ext_mask(&self) -> Option<c_uint> {
+        self.ext_mask
+    }
+
+    /// Sets the extended filter mask sent with queries to the kernel.
+    ///
+    /// This is the `IFLA_EXT_MASK` attribute that selects which optional
+    /// sets of data the kernel includes when reporting on the link. It is
+    /// a combination of the `EXT_FILTER_*` flags. For example,
+    /// [`EXT_FILTER_VF`] (the default) asks for information about any
+    /// SR-IOV virtual functions of the device, while
+    /// [`EXT_FILTER_SKIP_STATS`] asks the kernel to leave out the link
+    /// statistics.
+    ///
+    /// Setting this to `None` omits the attribute entirely, in which case
+    /// the kernel reports just its default set of attributes.
+    pub fn set_ext_mask<M>(&mut self, ext_mask: M)
+    where
+        M: Into<Option<c_uint>>,
+    {
+        self.ext_mask = ext_mask.into();
+    }
+
+    /// Gets the time to wait for the kernel to respond to a request.
+    ///
+    /// A value of `None` means to wait indefinitely.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Sets the time to wait for the kernel to respond to a request.
+    ///
+    /// This defaults to [`DEFAULT_NL_TIMEOUT`]. If the kernel doesn't
+    /// respond in time, the request fails with [`CanNlError::Timeout`].
+    /// Setting this to `None` waits indefinitely.
+    ///
+    /// Any cached socket is closed, so that the next request opens one
+    /// with the new timeout.
+    pub fn set_timeout<D>(&mut self, timeout: D)
+    where
+        D: Into<Option<Duration>>,
+    {
+        self.timeout = timeout.into();
+        if let Ok(sock) = self.sock.get_mut() {
+            *sock = None;
+        }
+    }
+
+    /// Whether the kernel is asked to strictly check requests.
+    pub fn strict_check(&self) -> bool {
+        self.strict_check
+    }
+
+    /// Sets whether the kernel strictly checks requests
+    /// (`NETLINK_GET_STRICT_CHK`).
+    ///
+    /// With strict checking, the kernel rejects requests with malformed
+    /// headers or unexpected attributes, rather than silently ignoring
+    /// them. That's mostly useful when developing support for new
+    /// attributes, since the netlink API for CAN is sparsely documented.
+    /// Older kernels (before 4.20) don't support it, and fail requests
+    /// with `ENOPROTOOPT`.
+    ///
+    /// Any cached socket is closed, so that the next request opens one
+    /// with the new setting.
+    pub fn set_strict_check(&mut self, on: bool) {
+        self.strict_check = on;
+        if let Ok(sock) = self.sock.get_mut() {
+            *sock = None;
+        }
+    }
+
+    /// Whether the netlink sockets for the interface are bound to the
+    /// process' PID.
+    pub fn bind_pid(&self) -> bool {
+        self.bind_pid
+    }
+
+    /// Sets whether the netlink sockets for the interface are bound to
+    /// the process' PID.
+    ///
+    /// By default, a socket is bound to the PID, falling back to a port
+    /// picked by the kernel if the PID is already taken. Some sandboxed
+    /// environments and container runtimes reject binding to the PID
+    /// outright, so turning this off always lets the kernel pick the
+    /// port.
+    ///
+    /// Any cached socket is closed, so that the next request opens one
+    /// with the new setting.
+    pub fn set_bind_pid(&mut self, on: bool) {
+        self.bind_pid = on;
+        if let Ok(sock) = self.sock.get_mut() {
+            *sock = None;
+        }
+    }
+
+    /// Looks up the name of the interface with the specified index.
+    ///
+    /// This is the reverse of the lookup done by [open](CanInterface::open),
+    /// and is useful for rendering a human-readable name when only the
+    /// index is known.
+    pub fn index_to_name(index: u32) -> NlResult<String> {
+        let mut buf = [0 as c_char; libc::IFNAMSIZ];
+        let name = unsafe { libc::if_indextoname(index, buf.as_mut_ptr()) };
+        if name.is_null() {
+            return Err(io::Error::last_os_error().into());
+        }
+        let name = unsafe { CStr::from_ptr(buf.as_ptr()) };
+        Ok(name.to_string_lossy().into_owned())
     }
 
     /// Creates an `Ifinfomsg` for this CAN interface from a buffer
@@ -404,11 +1245,97 @@ impl CanInterface {
     }
 
     /// Sends an info message to the kernel.
-    fn send_info_msg(msg_type: Rtm, info: Ifinfomsg, additional_flags: &[NlmF]) -> NlResult<()> {
-        let mut nl = Self::open_route_socket()?;
+    fn send_info_msg(
+        &self,
+        msg_type: Rtm,
+        info: Ifinfomsg,
+        additional_flags: &[NlmF],
+    ) -> NlResult<()> {
+        if let Some(msgs) = &self.dry_run {
+            let hdr = Self::info_msg_hdr(msg_type, info, additional_flags);
+            hdr.to_bytes(&mut Cursor::new(Vec::new()))?;
+            if let Ok(mut msgs) = msgs.lock() {
+                msgs.push(hdr);
+            }
+            return Ok(());
+        }
 
-        // prepare message
-        let hdr = Nlmsghdr::new(
+        self.with_route_socket(|sock| {
+            Self::send_info_msg_on(sock, msg_type, info, additional_flags)
+        })
+    }
+
+    /// Runs a request on a netlink socket for this interface.
+    ///
+    /// If socket caching is on, this uses the cached socket, opening it
+    /// first if needed. Otherwise it opens a new socket just for the
+    /// request.
+    fn with_route_socket<F, R>(&self, f: F) -> NlResult<R>
+    where
+        F: FnOnce(&mut NlSocketHandle) -> NlResult<R>,
+    {
+        if !self.cache_socket {
+            let mut sock = self.open_socket()?;
+            return f(&mut sock);
+        }
+
+        let mut cached = self
+            .sock
+            .lock()
+            .map_err(|_| CanNlError::Netlink(NlError::Msg("Socket lock poisoned".into())))?;
+
+        let mut sock = match cached.take() {
+            Some(sock) => sock,
+            None => Box::new(self.open_socket()?),
+        };
+
+        let res = f(&mut sock);
+        if res.is_ok() {
+            *cached = Some(sock);
+        }
+        res
+    }
+
+    /// Opens a new netlink socket with the options for this interface.
+    fn open_socket(&self) -> NlResult<NlSocketHandle> {
+        let sock = Self::open_route_socket_with(self.timeout, self.bind_pid)?;
+        if self.strict_check {
+            let on: c_int = 1;
+            let ret = unsafe {
+                libc::setsockopt(
+                    sock.as_raw_fd(),
+                    rt::SOL_NETLINK,
+                    rt::NETLINK_GET_STRICT_CHK,
+                    &on as *const c_int as *const libc::c_void,
+                    size_of::<c_int>() as libc::socklen_t,
+                )
+            };
+            if ret < 0 {
+                return Err(io::Error::last_os_error().into());
+            }
+        }
+        Ok(sock)
+    }
+
+    /// Sends an info message to the kernel over the specified socket.
+    fn send_info_msg_on(
+        sock: &mut NlSocketHandle,
+        msg_type: Rtm,
+        info: Ifinfomsg,
+        additional_flags: &[NlmF],
+    ) -> NlResult<()> {
+        let hdr = Self::info_msg_hdr(msg_type, info, additional_flags);
+        Self::send_and_read_ack(sock, hdr)
+    }
+
+    /// Wraps an info message in a netlink header for a request that
+    /// expects an ACK.
+    fn info_msg_hdr(
+        msg_type: Rtm,
+        info: Ifinfomsg,
+        additional_flags: &[NlmF],
+    ) -> Nlmsghdr<Rtm, Ifinfomsg> {
+        Nlmsghdr::new(
             None,
             msg_type,
             {
@@ -421,9 +1348,7 @@ impl CanInterface {
             None,
             None,
             NlPayload::Payload(info),
-        );
-        // send the message
-        Self::send_and_read_ack(&mut nl, hdr)
+        )
     }
 
     /// Sends a message down a netlink socket, and checks if an ACK was
@@ -433,42 +1358,135 @@ impl CanInterface {
         T: NlType + Debug,
         P: ToBytes + Debug,
     {
-        sock.send(msg)?;
+        Self::send_msg(sock, msg)?;
 
         // This will actually produce an Err if the response is a netlink error,
         // no need to match.
-        if let Some(Nlmsghdr {
-            nl_payload: NlPayload::Ack(_),
-            ..
-        }) = sock.recv()?
-        {
-            Ok(())
-        } else {
-            Err(NlError::NoAck)
-        }
+        Self::check_ack(Self::recv_msg::<u16, Buffer>(sock)?)
     }
 
-    /// Opens a new netlink socket, bound to this process' PID.
-    /// The function is generic to allow for usage in contexts where NlError
-    /// has specific, non-default, generic parameters.
-    fn open_route_socket<T, P>() -> Result<NlSocketHandle, NlError<T, P>> {
-        // retrieve PID
-        let pid = unistd::Pid::this().as_raw() as u32;
-
-        // open and bind socket
-        // groups is set to None(0), because we want no notifications
-        let sock = NlSocketHandle::connect(NlFamily::Route, Some(pid), &[])?;
+    /// Sends a message to the kernel.
+    ///
+    /// As with [recv_msg](CanInterface::recv_msg), a send that is
+    /// interrupted by a signal (`EINTR`) is retried a few times. The socket
+    /// takes the message by value, so it's serialized up front, and parsed
+    /// back into a raw message for each attempt. The raw message has the
+    /// same header flags, so the socket still expects an ACK if one was
+    /// requested.
+    fn send_msg<T, P>(sock: &mut NlSocketHandle, msg: Nlmsghdr<T, P>) -> NlResult<()>
+    where
+        T: NlType + Debug,
+        P: ToBytes + Debug,
+    {
+        let mut buf = Cursor::new(Vec::new());
+        msg.to_bytes(&mut buf)?;
+        let buf = buf.into_inner();
+
+        let mut retries = 0;
+        loop {
+            let raw = Nlmsghdr::<u16, Buffer>::from_bytes(&mut Cursor::new(&buf[..]))?;
+            match sock.send(raw) {
+                Err(SerError::Wrapped(WrappedError::IOError(err)))
+                    if err.kind() == io::ErrorKind::Interrupted && retries < MAX_EINTR_RETRIES =>
+                {
+                    retries += 1;
+                }
+                res => return Ok(res?),
+            }
+        }
+    }
+
+    /// Receives a message from the kernel.
+    ///
+    /// If the wait for the message is interrupted by a signal (`EINTR`),
+    /// this retries a few times before giving up, so that a process that
+    /// handles signals doesn't see spurious errors.
+    fn recv_msg<T, P>(sock: &mut NlSocketHandle) -> NlResult<Option<Nlmsghdr<T, P>>>
+    where
+        T: NlType + Debug,
+        P: for<'a> FromBytesWithInput<'a, Input = usize> + Debug,
+    {
+        let mut retries = 0;
+        loop {
+            match sock.recv::<'_, T, P>() {
+                Err(NlError::Wrapped(WrappedError::IOError(err)))
+                    if err.kind() == io::ErrorKind::Interrupted && retries < MAX_EINTR_RETRIES =>
+                {
+                    retries += 1;
+                }
+                res => return Ok(res?),
+            }
+        }
+    }
+
+    /// Checks that the response to a request is an ACK.
+    fn check_ack(msg: Option<Nlmsghdr<u16, Buffer>>) -> NlResult<()> {
+        match msg {
+            Some(Nlmsghdr {
+                nl_payload: NlPayload::Ack(_),
+                ..
+            }) => Ok(()),
+            Some(_) => Err(CanNlError::NoAck),
+            None => Err(CanNlError::NoMessage),
+        }
+    }
+
+    /// Opens a new netlink socket, bound to this process' PID.
+    ///
+    /// Only one socket in the process can be bound to the PID at a time,
+    /// so if it's already taken, such as by a cached socket, the kernel is
+    /// left to assign a unique port instead.
+    ///
+    /// The `timeout` is the longest that a receive on the socket will wait
+    /// for the kernel to respond. `None` means to wait forever.
+    fn open_route_socket(timeout: Option<Duration>) -> NlResult<NlSocketHandle> {
+        Self::open_route_socket_with(timeout, true)
+    }
+
+    /// Opens a new netlink socket, optionally bound to this process' PID.
+    ///
+    /// If `bind_pid` is false, the kernel always assigns the port.
+    fn open_route_socket_with(
+        timeout: Option<Duration>,
+        bind_pid: bool,
+    ) -> NlResult<NlSocketHandle> {
+        // groups is set to None(0), because we want no notifications
+        let sock = if bind_pid {
+            // retrieve PID
+            let pid = unistd::Pid::this().as_raw() as u32;
+
+            match NlSocketHandle::connect(NlFamily::Route, Some(pid), &[]) {
+                Err(err) if err.raw_os_error() == Some(libc::EADDRINUSE) => {
+                    NlSocketHandle::connect(NlFamily::Route, None, &[])?
+                }
+                res => res?,
+            }
+        } else {
+            NlSocketHandle::connect(NlFamily::Route, None, &[])?
+        };
+
+        let fd = unsafe { BorrowedFd::borrow_raw(sock.as_raw_fd()) };
+        SockRef::from(&fd).set_read_timeout(timeout)?;
         Ok(sock)
     }
 
     /// Sends a query to the kernel and returns the response info message
     /// to the caller.
-    fn query_details(&self) -> Result<Option<Nlmsghdr<Rtm, Ifinfomsg>>, NlInfoError> {
-        let mut sock = Self::open_route_socket()?;
+    fn query_details(&self) -> NlResult<Option<Nlmsghdr<Rtm, Ifinfomsg>>> {
+        let hdr = self.details_msg_hdr()?;
+        self.with_route_socket(|sock| {
+            Self::send_msg(sock, hdr)?;
+            Self::recv_msg::<Rtm, Ifinfomsg>(sock)
+        })
+    }
 
+    /// Creates the request message to query the details of the interface.
+    fn details_msg_hdr(&self) -> NlResult<Nlmsghdr<Rtm, Ifinfomsg>> {
         let info = self.info_msg({
             let mut buffer = RtBuffer::new();
-            buffer.push(Rtattr::new(None, Ifla::ExtMask, rt::EXT_FILTER_VF).unwrap());
+            if let Some(ext_mask) = self.ext_mask {
+                buffer.push(Rtattr::new(None, Ifla::ExtMask, ext_mask)?);
+            }
             buffer
         });
 
@@ -480,37 +1498,205 @@ impl CanInterface {
             None,
             NlPayload::Payload(info),
         );
+        Ok(hdr)
+    }
+
+    /// Parses the response to a details query.
+    fn details_from_msg(
+        &self,
+        msg: Option<Nlmsghdr<Rtm, Ifinfomsg>>,
+    ) -> NlResult<InterfaceDetails> {
+        match msg {
+            Some(msg_hdr) => match msg_hdr.get_payload() {
+                Ok(payload) => {
+                    self.check_index(payload.ifi_index as u32)?;
+                    InterfaceDetails::try_from(payload)
+                }
+                Err(_) => Ok(InterfaceDetails::new(self.if_index)),
+            },
+            None => Err(CanNlError::NoMessage),
+        }
+    }
 
-        sock.send(hdr)?;
-        sock.recv::<'_, Rtm, Ifinfomsg>()
+    /// Checks that an interface index reported by the kernel is the one
+    /// for this interface.
+    fn check_index(&self, actual: u32) -> NlResult<()> {
+        if actual != self.if_index {
+            return Err(CanNlError::IndexMismatch {
+                expected: self.if_index,
+                actual,
+            });
+        }
+        Ok(())
     }
 
     /// Bring down this interface.
     ///
     /// Use a netlink control socket to set the interface status to "down".
     pub fn bring_down(&self) -> NlResult<()> {
-        // Specific iface down info
-        let info = Ifinfomsg::down(
+        self.send_info_msg(Rtm::Newlink, self.down_msg(), &[])
+    }
+
+    /// Bring down this interface before reconfiguring it, and verify that
+    /// it went down.
+    ///
+    /// The ACK from the kernel only says that the request was accepted, so
+    /// this reads back the state of the interface afterward, and fails if
+    /// it is still up. That way a reconfiguration doesn't go ahead only to
+    /// be rejected with `EBUSY`. In dry-run mode, the state is not checked.
+    pub fn bring_down_for_reconfig(&self) -> NlResult<()> {
+        self.bring_down()?;
+        if !self.is_dry_run() && self.details()?.is_up {
+            return Err(CanNlError::StillUp);
+        }
+        Ok(())
+    }
+
+    /// Creates the info message to bring the interface down.
+    fn down_msg(&self) -> Ifinfomsg {
+        Ifinfomsg::down(
             RtAddrFamily::Unspecified,
             Arphrd::Netrom,
             self.if_index as c_int,
             RtBuffer::new(),
-        );
-        Self::send_info_msg(Rtm::Newlink, info, &[])
+        )
     }
 
     /// Bring up this interface
     ///
     /// Brings the interface up by settings its "up" flag enabled via netlink.
     pub fn bring_up(&self) -> NlResult<()> {
-        // Specific iface up info
-        let info = Ifinfomsg::up(
+        self.send_info_msg(Rtm::Newlink, self.up_msg(), &[])
+    }
+
+    /// Creates the info message to bring the interface up.
+    fn up_msg(&self) -> Ifinfomsg {
+        Ifinfomsg::up(
             RtAddrFamily::Unspecified,
             Arphrd::Netrom,
             self.if_index as c_int,
             RtBuffer::new(),
-        );
-        Self::send_info_msg(Rtm::Newlink, info, &[])
+        )
+    }
+
+    /// Bring up this interface for the lifetime of the returned guard.
+    ///
+    /// The interface is brought back down when the guard is dropped, even
+    /// if that happens while unwinding from a panic. This is useful in
+    /// tests and other scoped configurations to avoid leaving an interface
+    /// up by accident.
+    pub fn bring_up_scoped(&self) -> NlResult<InterfaceUpGuard<'_>> {
+        self.bring_up()?;
+        Ok(InterfaceUpGuard { iface: self })
+    }
+
+    /// Reconfigures the interface while it is down.
+    ///
+    /// Most CAN parameters, like the bit timing and control modes, can
+    /// only be changed while the interface is down. This brings the
+    /// interface down, runs the closure to make the changes, and then
+    /// restores the interface to its original up/down state, even if the
+    /// closure fails. An error from the closure takes precedence over
+    /// one from bringing the interface back up.
+    ///
+    /// In dry-run mode, the interface isn't queried, and is assumed to be
+    /// up, so the messages to bring it down and back up are recorded
+    /// around those from the closure.
+    ///
+    /// ```no_run
+    /// # use socketcan::CanInterface;
+    /// # fn main() -> Result<(), socketcan::CanNlError> {
+    /// let iface = CanInterface::open_iface(42);
+    /// iface.reconfigure(|iface| iface.set_bitrate(250_000, None))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn reconfigure<F>(&self, f: F) -> NlResult<()>
+    where
+        F: FnOnce(&Self) -> NlResult<()>,
+    {
+        let was_up = self.is_dry_run() || self.details()?.is_up;
+        if was_up {
+            self.bring_down_for_reconfig()?;
+        }
+
+        let res = f(self);
+
+        if was_up {
+            let up_res = self.bring_up();
+            res?;
+            up_res
+        } else {
+            res
+        }
+    }
+
+    /// Applies a complete configuration to the interface and brings it up.
+    ///
+    /// The interface is brought down if needed, all of the settings are
+    /// sent in a single request, and then the interface is brought up.
+    /// This is the one call to set up a bus from scratch.
+    ///
+    /// If the configuration is rejected, the interface is put back the
+    /// way it was: the settings it had before are re-applied, and it is
+    /// brought back up only if it was up to begin with. The error from
+    /// the configuration is returned, unless restoring the interface also
+    /// failed, in which case both errors are returned in a
+    /// [`CanNlError::RestoreFailed`].
+    ///
+    /// In dry-run mode, the interface isn't queried for its prior
+    /// settings. It is assumed to be up, so the messages to bring it down,
+    /// apply the configuration, and bring it back up are recorded. Since
+    /// nothing is sent, there is nothing to restore.
+    ///
+    /// ```no_run
+    /// # use socketcan::nl::{CanInterface, CanInterfaceConfig, Mtu};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let iface = CanInterface::open("can0")?;
+    /// iface.apply_config_with_restart(
+    ///     CanInterfaceConfig::default()
+    ///         .bitrate(500_000)
+    ///         .restart_ms(100),
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn apply_config_with_restart(&self, config: CanInterfaceConfig) -> NlResult<()> {
+        if self.is_dry_run() {
+            self.bring_down_for_reconfig()?;
+            config.apply(self)?;
+            return self.bring_up();
+        }
+
+        let details = self.details()?;
+        let prior = CanInterfaceConfig::from_details(&details);
+
+        if details.is_up {
+            self.bring_down_for_reconfig()?;
+        }
+
+        if let Err(err) = config.apply(self) {
+            let restored = prior.apply(self);
+            let up = if details.is_up {
+                self.bring_up()
+            } else {
+                Ok(())
+            };
+            return Err(match restored.and(up) {
+                Ok(()) => err,
+                Err(restore_err) => CanNlError::RestoreFailed {
+                    error: Box::new(err),
+                    restore_error: Box::new(restore_err),
+                },
+            });
+        }
+        self.bring_up()
     }
 
     /// Create a virtual CAN (VCAN) interface.
@@ -523,7 +1709,66 @@ impl CanInterface {
     /// PRIVILEGED: This requires root privilege.
     ///
     pub fn create_vcan(name: &str, index: Option<u32>) -> NlResult<Self> {
-        Self::create(name, index, "vcan")
+        Self::create(name, index, KIND_VCAN)
+    }
+
+    /// Create a virtual CAN (VCAN) interface that is ready for FD frames.
+    ///
+    /// This is the same as [create_vcan](CanInterface::create_vcan), but
+    /// the interface is created with the FD MTU, in the same request,
+    /// rather than needing to set it afterward.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn create_vcan_fd(name: &str, index: Option<u32>) -> NlResult<Self> {
+        let mtu = Mtu::Fd as u32;
+        let attrs = vec![Rtattr::new(None, Ifla::Mtu, &mtu.to_ne_bytes()[..])?];
+        Self::create_with_attrs(name, index, KIND_VCAN, attrs)
+    }
+
+    /// Create a virtual CAN (VCAN) interface and bring it up, so that it is
+    /// ready to use.
+    ///
+    /// If the interface can't be brought up, it is deleted again.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn create_vcan_up(name: &str, index: Option<u32>) -> NlResult<Self> {
+        let iface = Self::create_vcan(name, index)?;
+        if let Err(err) = iface.bring_up() {
+            let _ = iface.delete_ref();
+            return Err(err);
+        }
+        Ok(iface)
+    }
+
+    /// Create a virtual CAN (VCAN) interface, first deleting any existing
+    /// VCAN interface with the same name.
+    ///
+    /// This is mainly for test fixtures, which might otherwise fail to
+    /// create their interfaces after a previous run crashed and left them
+    /// behind. An existing interface that is not a VCAN is never deleted;
+    /// it results in an `EEXIST` error instead.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn recreate_vcan(name: &str) -> NlResult<Self> {
+        match Self::open(name) {
+            Ok(iface) => {
+                if iface.kind()?.as_deref() != Some(KIND_VCAN) {
+                    return Err(CanNlError::Kernel {
+                        errno: libc::EEXIST,
+                    });
+                }
+                match iface.delete_ref() {
+                    Err(err) if err.errno() != Some(libc::ENODEV) => return Err(err),
+                    _ => (),
+                }
+            }
+            Err(nix::Error::ENODEV) => (),
+            Err(err) => return Err(io::Error::from(err).into()),
+        }
+        Self::create_vcan(name, None)
     }
 
     /// Create an interface of the given kind.
@@ -536,10 +1781,146 @@ impl CanInterface {
     where
         I: Into<Option<u32>>,
     {
+        Self::create_with_attrs(name, index.into(), kind, Vec::new())
+    }
+
+    /// Create an interface of the given kind, with additional link
+    /// attributes, and open it.
+    fn create_with_attrs(
+        name: &str,
+        index: Option<u32>,
+        kind: &str,
+        attrs: Vec<Rtattr<Ifla, Buffer>>,
+    ) -> NlResult<Self> {
+        Self::send_new_link(name, index, kind, attrs)?;
+
+        if let Some(if_index) = index {
+            // Make sure that the name now refers to the requested index
+            let iface = Self::open_iface(if_index);
+            if let Ok(actual) = if_nametoindex(name) {
+                iface.check_index(actual)?;
+            }
+            Ok(iface)
+        } else {
+            // Unfortunately netlink does not return the the if_index assigned to the interface.
+            if let Ok(if_index) = if_nametoindex(name) {
+                Ok(Self::open_iface(if_index))
+            } else {
+                Err(CanNlError::Netlink(NlError::Msg(
+                    "Interface must have been deleted between request and this if_nametoindex"
+                        .into(),
+                )))
+            }
+        }
+    }
+
+    /// Create an interface of the given kind in another network namespace.
+    ///
+    /// The namespace is specified by an open file descriptor that refers
+    /// to it, such as one obtained by opening a file under `/var/run/netns`
+    /// or `/proc/<pid>/ns/net`.
+    ///
+    /// Since the new interface does not exist in the caller's namespace,
+    /// it can not be managed from here, and so no `CanInterface` is
+    /// returned.
+    ///
+    /// Note that the length of the name is capped by ```libc::IFNAMSIZ```.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn create_in_netns<I>(name: &str, index: I, kind: &str, netns_fd: RawFd) -> NlResult<()>
+    where
+        I: Into<Option<u32>>,
+    {
+        let attrs = vec![Rtattr::new(None, Ifla::NetNsFd, netns_fd as u32)?];
+        Self::send_new_link(name, index.into(), kind, attrs)
+    }
+
+    /// Create a pair of connected virtual CAN tunnel (VXCAN) interfaces.
+    ///
+    /// Frames sent on either interface are received on the other. The
+    /// peer, named `peer_name`, can be placed directly into another network
+    /// namespace, specified by an open file descriptor that refers to it,
+    /// such as one obtained by opening a file under `/var/run/netns` or
+    /// `/proc/<pid>/ns/net`. This is the usual way to give a container a
+    /// CAN interface connected to the host, and it is done in a single
+    /// request, so there is no window in which the peer is visible in the
+    /// wrong namespace.
+    ///
+    /// This returns the local interface, `name`.
+    ///
+    /// Note that the length of the names is capped by ```libc::IFNAMSIZ```.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn create_vxcan_pair(
+        name: &str,
+        peer_name: &str,
+        peer_netns_fd: Option<RawFd>,
+    ) -> NlResult<Self> {
+        if peer_name.len() > libc::IFNAMSIZ {
+            return Err(CanNlError::Netlink(NlError::Msg(
+                "Interface name too long".into(),
+            )));
+        }
+
+        // The peer is described by a complete link info message
+        let peer = Ifinfomsg::new(
+            RtAddrFamily::Unspecified,
+            Arphrd::Netrom,
+            0,
+            IffFlags::empty(),
+            IffFlags::empty(),
+            {
+                let mut buffer = RtBuffer::new();
+                buffer.push(Rtattr::new(None, Ifla::Ifname, peer_name)?);
+                if let Some(netns_fd) = peer_netns_fd {
+                    buffer.push(Rtattr::new(None, Ifla::NetNsFd, netns_fd as u32)?);
+                }
+                buffer
+            },
+        );
+        let mut peer_bytes = Cursor::new(Vec::new());
+        peer.to_bytes(&mut peer_bytes)?;
+
+        let mut data = Rtattr::new(None, IflaInfo::Data, Buffer::new())?;
+        data.add_nested_attribute(&Rtattr::new(
+            None,
+            VxcanInfo::Peer,
+            &peer_bytes.get_ref()[..],
+        )?)?;
+
+        Self::send_new_link_with_data(name, None, KIND_VXCAN, Some(data), Vec::new())?;
+        Self::open(name).map_err(|err| io::Error::from(err).into())
+    }
+
+    /// Sends a request to the kernel to create a new interface.
+    ///
+    /// Any additional attributes in `attrs` are sent along with the name
+    /// and kind of the interface.
+    fn send_new_link(
+        name: &str,
+        index: Option<u32>,
+        kind: &str,
+        attrs: Vec<Rtattr<Ifla, Buffer>>,
+    ) -> NlResult<()> {
+        Self::send_new_link_with_data(name, index, kind, None, attrs)
+    }
+
+    /// Sends a request to the kernel to create a new interface, with
+    /// kind-specific data nested in the link info.
+    fn send_new_link_with_data(
+        name: &str,
+        index: Option<u32>,
+        kind: &str,
+        info_data: Option<Rtattr<IflaInfo, Buffer>>,
+        attrs: Vec<Rtattr<Ifla, Buffer>>,
+    ) -> NlResult<()> {
         if name.len() > libc::IFNAMSIZ {
-            return Err(NlError::Msg("Interface name too long".into()));
+            return Err(CanNlError::Netlink(NlError::Msg(
+                "Interface name too long".into(),
+            )));
         }
-        let index = index.into();
 
         let info = Ifinfomsg::new(
             RtAddrFamily::Unspecified,
@@ -552,114 +1933,593 @@ impl CanInterface {
                 buffer.push(Rtattr::new(None, Ifla::Ifname, name)?);
                 let mut linkinfo = Rtattr::new(None, Ifla::Linkinfo, Vec::<u8>::new())?;
                 linkinfo.add_nested_attribute(&Rtattr::new(None, IflaInfo::Kind, kind)?)?;
+                if let Some(data) = info_data {
+                    linkinfo.add_nested_attribute(&data)?;
+                }
                 buffer.push(linkinfo);
+                for attr in attrs {
+                    buffer.push(attr);
+                }
                 buffer
             },
         );
-        Self::send_info_msg(Rtm::Newlink, info, &[NlmF::Create, NlmF::Excl])?;
-
-        if let Some(if_index) = index {
-            Ok(Self { if_index })
-        } else {
-            // Unfortunately netlink does not return the the if_index assigned to the interface.
-            if let Ok(if_index) = if_nametoindex(name) {
-                Ok(Self { if_index })
-            } else {
-                Err(NlError::Msg(
-                    "Interface must have been deleted between request and this if_nametoindex"
-                        .into(),
-                ))
-            }
-        }
+        let mut sock = Self::open_route_socket(Some(DEFAULT_NL_TIMEOUT))?;
+        Self::send_info_msg_on(&mut sock, Rtm::Newlink, info, &[NlmF::Create, NlmF::Excl])
     }
 
     /// Delete the interface.
     ///
     /// PRIVILEGED: This requires root privilege.
     ///
-    pub fn delete(self) -> Result<(), (Self, NlError)> {
-        let info = self.info_msg(RtBuffer::new());
-        match Self::send_info_msg(Rtm::Dellink, info, &[]) {
+    pub fn delete(self) -> Result<(), (Self, CanNlError)> {
+        match self.delete_ref() {
             Ok(()) => Ok(()),
             Err(err) => Err((self, err)),
         }
     }
 
-    /// Attempt to query detailed information on the interface.
-    pub fn details(&self) -> Result<InterfaceDetails, NlInfoError> {
-        match self.query_details()? {
-            Some(msg_hdr) => {
-                let mut info = InterfaceDetails::new(self.if_index);
-
-                if let Ok(payload) = msg_hdr.get_payload() {
-                    info.is_up = payload.ifi_flags.contains(&Iff::Up);
-
-                    for attr in payload.rtattrs.iter() {
-                        match attr.rta_type {
-                            Ifla::Ifname => {
-                                // Note: Use `CStr::from_bytes_until_nul` when MSRV >= 1.69
-                                info.name = CStr::from_bytes_with_nul(attr.rta_payload.as_ref())
-                                    .map(|s| s.to_string_lossy().into_owned())
-                                    .ok();
-                            }
-                            Ifla::Mtu => {
-                                info.mtu = attr
-                                    .get_payload_as::<u32>()
-                                    .ok()
-                                    .and_then(|mtu| Mtu::try_from(mtu).ok());
-                            }
-                            Ifla::Linkinfo => {
-                                info.can = InterfaceCanParams::try_from(attr)?;
-                            }
-                            _ => (),
-                        }
-                    }
-                }
-
-                Ok(info)
-            }
-            None => Err(NlError::NoAck),
-        }
+    /// Delete the interface without consuming this object.
+    ///
+    /// This is the same as [delete](CanInterface::delete), but is more
+    /// convenient when the interface is held in a shared structure or the
+    /// deletion might need to be retried.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn delete_ref(&self) -> NlResult<()> {
+        let info = self.info_msg(RtBuffer::new());
+        self.send_info_msg(Rtm::Dellink, info, &[])
     }
 
-    /// Set the MTU of this interface.
+    /// Delete all of the virtual CAN (VCAN) interfaces whose names start
+    /// with the `prefix`.
+    ///
+    /// This is meant for cleaning up after tests, such as ones that
+    /// crashed and leaked their interfaces. Only VCAN interfaces are ever
+    /// deleted. Interfaces that disappear before they can be deleted are
+    /// skipped. This returns the number of interfaces that were deleted.
     ///
     /// PRIVILEGED: This requires root privilege.
     ///
-    pub fn set_mtu(&self, mtu: Mtu) -> NlResult<()> {
-        let mtu = mtu as u32;
-        let info = self.info_msg({
-            let mut buffer = RtBuffer::new();
-            buffer.push(Rtattr::new(None, Ifla::Mtu, &mtu.to_ne_bytes()[..])?);
-            buffer
-        });
-        Self::send_info_msg(Rtm::Newlink, info, &[])
+    pub fn delete_matching(prefix: &str) -> NlResult<usize> {
+        let mut count = 0;
+        for details in Self::list()? {
+            let matches = details.kind.as_deref() == Some(KIND_VCAN)
+                && details
+                    .name
+                    .as_deref()
+                    .is_some_and(|name| name.as_bytes().starts_with(prefix.as_bytes()));
+            if !matches {
+                continue;
+            }
+            match Self::open_iface(details.index).delete_ref() {
+                Ok(()) => count += 1,
+                Err(err) if err.errno() == Some(libc::ENODEV) => (),
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(count)
     }
 
-    /// Set a CAN-specific parameter.
+    /// Attempt to query detailed information on the interface.
+    pub fn details(&self) -> NlResult<InterfaceDetails> {
+        let msg = self.query_details()?;
+        self.details_from_msg(msg)
+    }
+
+    /// Gets the details of all the CAN interfaces on the system.
     ///
-    /// This send a netlink message down to the kernel to set an attribute
-    /// in the link info, such as bitrate, control modes, etc
+    /// This uses a single netlink dump request, and each entry includes the
+    /// full set of CAN parameters that the kernel reported for it, so no
+    /// follow-up queries are needed for the individual interfaces.
+    pub fn list() -> NlResult<Vec<InterfaceDetails>> {
+        let mut sock = Self::open_route_socket(Some(DEFAULT_NL_TIMEOUT))?;
+        let links =
+            Self::dump_links(&mut sock, |payload| InterfaceDetails::try_from(payload))?.collect();
+        links
+    }
+
+    /// Gets the names of all the CAN interfaces on the system.
     ///
-    /// PRIVILEGED: This requires root privilege.
+    /// This is a lighter-weight version of [list](CanInterface::list) for
+    /// when only the names are needed, such as for a selection menu. It
+    /// uses the same dump request, but doesn't parse the CAN parameters.
+    pub fn list_names() -> NlResult<Vec<String>> {
+        let mut sock = Self::open_route_socket(Some(DEFAULT_NL_TIMEOUT))?;
+        let names = Self::dump_links(&mut sock, |payload| {
+            Ok(payload
+                .rtattrs
+                .iter()
+                .find(|attr| attr.rta_type == Ifla::Ifname)
+                .map(|attr| {
+                    os_string_from_nul_bytes(attr.rta_payload.as_ref())
+                        .to_string_lossy()
+                        .into_owned()
+                }))
+        })?
+        .filter_map(Result::transpose)
+        .collect();
+        names
+    }
+
+    /// Requests a dump of all the links on the system, and returns an
+    /// iterator that parses each of the CAN interfaces with `f`.
     ///
-    pub fn set_can_param<P>(&self, param_type: IflaCan, param: P) -> NlResult<()>
+    /// The messages are read from the socket and parsed lazily, as the
+    /// iterator is advanced, so they are never all buffered at once. An
+    /// error parsing one message is returned in its place, and the dump
+    /// carries on. An error reading from the socket ends the dump.
+    fn dump_links<'a, F, R>(
+        sock: &'a mut NlSocketHandle,
+        mut f: F,
+    ) -> NlResult<impl Iterator<Item = NlResult<R>> + 'a>
     where
-        P: ToBytes + neli::Size,
+        F: FnMut(&Ifinfomsg) -> NlResult<R> + 'a,
+        R: 'a,
     {
-        let info = self.info_msg({
-            let mut data = Rtattr::new(None, IflaInfo::Data, Buffer::new())?;
-            data.add_nested_attribute(&Rtattr::new(None, param_type, param)?)?;
+        let info = Ifinfomsg::new(
+            RtAddrFamily::Unspecified,
+            Arphrd::Netrom,
+            0,
+            IffFlags::empty(),
+            IffFlags::empty(),
+            {
+                let mut buffer = RtBuffer::new();
+                buffer.push(Rtattr::new(None, Ifla::ExtMask, EXT_FILTER_VF)?);
+                buffer
+            },
+        );
 
-            let mut link_info = Rtattr::new(None, Ifla::Linkinfo, Buffer::new())?;
-            link_info.add_nested_attribute(&Rtattr::new(None, IflaInfo::Kind, "can")?)?;
-            link_info.add_nested_attribute(&data)?;
+        let hdr = Nlmsghdr::new(
+            None,
+            Rtm::Getlink,
+            NlmFFlags::new(&[NlmF::Request, NlmF::Dump]),
+            None,
+            None,
+            NlPayload::Payload(info),
+        );
+        Self::send_msg(sock, hdr)?;
 
-            let mut rtattrs = RtBuffer::new();
-            rtattrs.push(link_info);
-            rtattrs
-        });
-        Self::send_info_msg(Rtm::Newlink, info, &[])
+        let mut failed = false;
+        let iter = sock
+            .iter::<Rtm, Ifinfomsg>(false)
+            .map_while(move |msg| {
+                if failed {
+                    return None;
+                }
+                let msg = match msg {
+                    Ok(msg) => msg,
+                    Err(err) => {
+                        failed = true;
+                        return Some(Some(Err(err.into())));
+                    }
+                };
+                Some(match msg.get_payload() {
+                    Ok(payload) if u16::from(payload.ifi_type) == libc::ARPHRD_CAN => {
+                        Some(f(payload))
+                    }
+                    _ => None,
+                })
+            })
+            .flatten();
+        Ok(iter)
+    }
+
+    /// Gets the full set of interface flags.
+    ///
+    /// These are the `IFF_*` flags, like "up", "running", "noarp", and
+    /// "loopback", as shown by `ip link`. Whereas [details] only reports
+    /// whether the interface is up, this gives all of the flags, which
+    /// can help to tell a virtual interface from real hardware.
+    ///
+    /// [details]: CanInterface::details
+    pub fn flags(&self) -> NlResult<IffFlags> {
+        let hdr = self.query_details()?.ok_or(CanNlError::NoMessage)?;
+        match hdr.nl_payload {
+            NlPayload::Payload(info) => Ok(info.ifi_flags),
+            _ => Ok(IffFlags::empty()),
+        }
+    }
+
+    /// Gets all of the CAN-specific parameters of the interface.
+    ///
+    /// This is the CAN counterpart to [details](CanInterface::details),
+    /// and parses all of the parameters from a single query, rather than
+    /// needing a separate request for each with
+    /// [can_param](CanInterface::can_param).
+    pub fn can_params(&self) -> NlResult<InterfaceCanParams> {
+        Ok(self.details()?.can)
+    }
+
+    /// Attempt to query just the name of the interface.
+    pub fn name(&self) -> NlResult<Option<OsString>> {
+        match self.query_details()? {
+            Some(msg_hdr) => {
+                let name = msg_hdr.get_payload().ok().and_then(|payload| {
+                    payload
+                        .rtattrs
+                        .iter()
+                        .find(|attr| attr.rta_type == Ifla::Ifname)
+                        .map(|attr| os_string_from_nul_bytes(attr.rta_payload.as_ref()))
+                });
+                Ok(name)
+            }
+            None => Err(CanNlError::NoMessage),
+        }
+    }
+
+    /// Gets the alias of the interface, if it has one.
+    ///
+    /// The alias is a free-form, human-readable label for the interface,
+    /// as shown by `ip link`.
+    pub fn alias(&self) -> NlResult<Option<String>> {
+        let alias = self.link_str_param(Ifla::Ifalias)?;
+        Ok(alias.filter(|alias| !alias.is_empty()))
+    }
+
+    /// Gets the name of the queue discipline (qdisc) attached to the
+    /// interface, such as "pfifo_fast".
+    ///
+    /// A qdisc that is unsuited to CAN traffic can be the reason that a busy
+    /// interface drops frames.
+    pub fn qdisc(&self) -> NlResult<Option<String>> {
+        self.link_str_param(Ifla::Qdisc)
+    }
+
+    /// Sets the alias of the interface.
+    ///
+    /// This attaches a human-readable label to the interface, like
+    /// "powertrain" or "chassis", that is kept by the kernel for as long
+    /// as the interface exists. An empty string removes the alias. The
+    /// kernel limits the alias to 255 bytes.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn set_alias(&self, alias: &str) -> NlResult<()> {
+        let info = self.info_msg({
+            let mut buffer = RtBuffer::new();
+            buffer.push(Rtattr::new(None, Ifla::Ifalias, alias)?);
+            buffer
+        });
+        self.send_info_msg(Rtm::Newlink, info, &[])
+    }
+
+    /// Gets the group that the interface belongs to.
+    ///
+    /// Interfaces are in the default group, zero, unless they've been
+    /// moved to another one.
+    pub fn group(&self) -> NlResult<Option<u32>> {
+        self.link_param::<u32>(Ifla::Group)
+    }
+
+    /// Sets the group that the interface belongs to.
+    ///
+    /// Groups let several interfaces be managed together, such as with
+    /// `ip link set group 2 up`, so the CAN buses of a vehicle subsystem
+    /// can be brought up or down at once.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn set_group(&self, group: u32) -> NlResult<()> {
+        let info = self.info_msg({
+            let mut buffer = RtBuffer::new();
+            buffer.push(Rtattr::new(None, Ifla::Group, group)?);
+            buffer
+        });
+        self.send_info_msg(Rtm::Newlink, info, &[])
+    }
+
+    /// Attempt to query an individual top-level link attribute of the
+    /// interface.
+    fn link_param<P>(&self, param: Ifla) -> NlResult<Option<P>>
+    where
+        P: for<'a> FromBytes<'a> + Clone,
+    {
+        if let Some(hdr) = self.query_details()? {
+            if let Ok(payload) = hdr.get_payload() {
+                for attr in payload.rtattrs.iter() {
+                    if attr.rta_type == param {
+                        return Ok(Some(attr.get_payload_as::<P>()?));
+                    }
+                }
+            }
+            Ok(None)
+        } else {
+            Err(CanNlError::NoMessage)
+        }
+    }
+
+    /// Attempt to query an individual top-level link attribute of the
+    /// interface that holds a string.
+    fn link_str_param(&self, param: Ifla) -> NlResult<Option<String>> {
+        match self.query_details()? {
+            Some(msg_hdr) => Ok(msg_hdr.get_payload().ok().and_then(|payload| {
+                payload
+                    .rtattrs
+                    .iter()
+                    .find(|attr| attr.rta_type == param)
+                    .map(|attr| {
+                        os_string_from_nul_bytes(attr.rta_payload.as_ref())
+                            .to_string_lossy()
+                            .into_owned()
+                    })
+            })),
+            None => Err(CanNlError::NoMessage),
+        }
+    }
+
+    /// Gets all of the raw attributes for the interface, for debugging.
+    ///
+    /// This returns each attribute as a pair of its type and its raw
+    /// payload bytes, exactly as they were sent by the kernel. The
+    /// top-level link (`IFLA_*`) attributes come first, followed by the
+    /// CAN-specific (`IFLA_CAN_*`) attributes nested in the link info.
+    /// Since the two sets of type numbers overlap, the nested attributes
+    /// can only be told apart by their position in the list.
+    pub fn raw_attributes(&self) -> NlResult<Vec<(u16, Vec<u8>)>> {
+        let hdr = self.query_details()?.ok_or(CanNlError::NoMessage)?;
+
+        let mut attrs = Vec::new();
+        let mut can_attrs = Vec::new();
+
+        if let Ok(payload) = hdr.get_payload() {
+            for top_attr in payload.rtattrs.iter() {
+                attrs.push((
+                    u16::from(top_attr.rta_type),
+                    top_attr.rta_payload.as_ref().to_vec(),
+                ));
+                if top_attr.rta_type == Ifla::Linkinfo {
+                    for info in top_attr.get_attr_handle::<IflaInfo>()?.get_attrs() {
+                        if info.rta_type == IflaInfo::Data {
+                            for attr in info.get_attr_handle::<IflaCan>()?.get_attrs() {
+                                can_attrs.push((
+                                    u16::from(attr.rta_type),
+                                    attr.rta_payload.as_ref().to_vec(),
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        attrs.append(&mut can_attrs);
+        Ok(attrs)
+    }
+
+    /// Sends a raw info message to the kernel and returns the raw payload
+    /// of the response.
+    ///
+    /// This is intended for experimenting with netlink attributes that the
+    /// crate doesn't support yet. The `info` message is sent as-is, as a
+    /// request of the type, `msg_type`, with the `flags` added to the
+    /// request flag. An error response from the kernel is returned as an
+    /// error, and a response with no payload, like an ACK, returns an empty
+    /// vector.
+    ///
+    /// Note that a request that gets no response from the kernel, like a
+    /// change without the `NlmF::Ack` flag, waits until the timeout.
+    ///
+    /// This requires the `experimental` feature, and is not part of the
+    /// stable API.
+    #[cfg(feature = "experimental")]
+    pub fn send_raw(&self, msg_type: Rtm, info: Ifinfomsg, flags: &[NlmF]) -> NlResult<Vec<u8>> {
+        let hdr = Nlmsghdr::new(
+            None,
+            msg_type,
+            {
+                let mut nl_flags = NlmFFlags::new(&[NlmF::Request]);
+                for flag in flags {
+                    nl_flags.set(flag);
+                }
+                nl_flags
+            },
+            None,
+            None,
+            NlPayload::Payload(info),
+        );
+
+        self.with_route_socket(|sock| {
+            Self::send_msg(sock, hdr)?;
+            match Self::recv_msg::<u16, Buffer>(sock)? {
+                Some(Nlmsghdr {
+                    nl_payload: NlPayload::Payload(buf),
+                    ..
+                }) => Ok(buf.as_ref().to_vec()),
+                Some(_) => Ok(Vec::new()),
+                None => Err(CanNlError::NoMessage),
+            }
+        })
+    }
+
+    /// Gets the index of the peer device for a linked interface.
+    ///
+    /// For a `vxcan` tunnel this is the index of the other half of the
+    /// pair. This is `None` for interfaces that have no peer.
+    pub fn peer_index(&self) -> NlResult<Option<u32>> {
+        self.link_param::<u32>(Ifla::Link)
+    }
+
+    /// Gets the index of the master device of the interface.
+    ///
+    /// This is the device, such as a bridge, that the interface has been
+    /// enslaved to. It is `None` when the interface is standalone, which
+    /// is usually the case for CAN interfaces.
+    pub fn master(&self) -> NlResult<Option<u32>> {
+        self.link_param::<u32>(Ifla::Master)
+    }
+
+    /// Gets the number of times that the carrier of the interface has
+    /// changed state.
+    ///
+    /// For a CAN controller, the carrier goes down when the controller
+    /// goes bus-off, and comes back up when it recovers. So this counter
+    /// climbing quickly is a sign of a flapping bus. It is `None` on
+    /// kernels that don't report it.
+    pub fn carrier_changes(&self) -> NlResult<Option<u32>> {
+        self.link_param::<u32>(Ifla::CarrierChanges)
+    }
+
+    /// Gets a snapshot of the traffic counters of the interface.
+    ///
+    /// The counters are cumulative. To get traffic rates, take two
+    /// snapshots some time apart and use [`StatsSnapshot::delta`]. This
+    /// is `None` if the kernel doesn't report the 64-bit link statistics.
+    pub fn statistics(&self) -> NlResult<Option<StatsSnapshot>> {
+        let hdr = self.query_details()?.ok_or(CanNlError::NoMessage)?;
+        let time = Instant::now();
+        Ok(hdr.get_payload().ok().and_then(|payload| {
+            payload
+                .rtattrs
+                .iter()
+                .find(|attr| attr.rta_type == Ifla::Stats64)
+                .map(|attr| StatsSnapshot::from_raw(time, attr.rta_payload.as_ref()))
+        }))
+    }
+
+    /// Gets the kind of link, such as "can", "vcan", or "vxcan".
+    ///
+    /// This is `None` for interfaces whose driver doesn't register a link
+    /// kind, such as `slcan`.
+    pub fn kind(&self) -> NlResult<Option<String>> {
+        Ok(self.details()?.kind)
+    }
+
+    /// Gets the kind of link along with the kind of its master link, if
+    /// the interface is enslaved to one, such as a bond.
+    ///
+    /// This is `None` for interfaces whose driver doesn't register a link
+    /// kind.
+    pub fn link_info(&self) -> NlResult<Option<(String, Option<String>)>> {
+        let details = self.details()?;
+        Ok(details.kind.map(|kind| (kind, details.slave_kind)))
+    }
+
+    /// Determines if this is a virtual CAN interface (`vcan` or `vxcan`)
+    /// rather than a physical CAN controller.
+    ///
+    /// Interfaces with an unknown kind are not considered virtual.
+    pub fn is_virtual(&self) -> NlResult<bool> {
+        Ok(matches!(
+            self.kind()?.as_deref(),
+            Some(KIND_VCAN | KIND_VXCAN)
+        ))
+    }
+
+    /// Gets the operational state of the interface.
+    ///
+    /// Whereas the `is_up` flag in the [details](CanInterface::details)
+    /// is the administrative state of the interface, this tells whether
+    /// the link is actually able to carry traffic. A CAN interface that
+    /// was brought up, but went bus-off, might be reported as
+    /// operationally down.
+    pub fn oper_state(&self) -> NlResult<Option<OperState>> {
+        Ok(self
+            .link_param::<u8>(Ifla::Operstate)?
+            .map(OperState::try_from)
+            .transpose()?)
+    }
+
+    /// Set the MTU of this interface.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn set_mtu(&self, mtu: Mtu) -> NlResult<()> {
+        self.set_raw_mtu(mtu as u32)
+    }
+
+    /// Set the MTU of this interface to an arbitrary value.
+    ///
+    /// This allows for MTU values that can't be represented by [`Mtu`],
+    /// such as those for CAN XL, on kernels and devices that support
+    /// them. The kernel rejects values that the device can't handle.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn set_raw_mtu(&self, mtu: u32) -> NlResult<()> {
+        let info = self.info_msg({
+            let mut buffer = RtBuffer::new();
+            buffer.push(Rtattr::new(None, Ifla::Mtu, &mtu.to_ne_bytes()[..])?);
+            buffer
+        });
+        self.send_info_msg(Rtm::Newlink, info, &[])
+    }
+
+    /// Move this interface into another network namespace.
+    ///
+    /// The namespace is specified by an open file descriptor that refers
+    /// to it, such as one obtained by opening a file under `/var/run/netns`
+    /// or `/proc/<pid>/ns/net`. This is typically used to hand a physical
+    /// CAN adapter over to a container.
+    ///
+    /// Once moved, the interface no longer exists in the caller's
+    /// namespace, and this object can no longer be used to manage it.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn move_to_netns(&self, netns_fd: RawFd) -> NlResult<()> {
+        let info = self.info_msg({
+            let mut buffer = RtBuffer::new();
+            buffer.push(Rtattr::new(None, Ifla::NetNsFd, netns_fd as u32)?);
+            buffer
+        });
+        self.send_info_msg(Rtm::Newlink, info, &[])
+    }
+
+    /// Set the hardware (link-layer) address of this interface.
+    ///
+    /// CAN interfaces normally have no hardware address, but some virtual
+    /// and bridged setups require one. The address must be between 1 and
+    /// `MAX_ADDR_LEN` (32) bytes long.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn set_hw_address(&self, addr: &[u8]) -> NlResult<()> {
+        // From linux/netdevice.h
+        const MAX_ADDR_LEN: usize = 32;
+
+        if addr.is_empty() || addr.len() > MAX_ADDR_LEN {
+            return Err(CanNlError::Netlink(NlError::Msg(
+                "Invalid hardware address length".into(),
+            )));
+        }
+
+        let info = self.info_msg({
+            let mut buffer = RtBuffer::new();
+            buffer.push(Rtattr::new(None, Ifla::Address, addr)?);
+            buffer
+        });
+        self.send_info_msg(Rtm::Newlink, info, &[])
+    }
+
+    /// Set a CAN-specific parameter.
+    ///
+    /// This send a netlink message down to the kernel to set an attribute
+    /// in the link info, such as bitrate, control modes, etc
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn set_can_param<P>(&self, param_type: IflaCan, param: P) -> NlResult<()>
+    where
+        P: ToBytes + neli::Size,
+    {
+        let param_name = can_param_name(&param_type);
+        let info = self.info_msg({
+            let mut data = Rtattr::new(None, IflaInfo::Data, Buffer::new())?;
+            data.add_nested_attribute(&Rtattr::new(None, param_type, param)?)?;
+
+            let mut link_info = Rtattr::new(None, Ifla::Linkinfo, Buffer::new())?;
+            link_info.add_nested_attribute(&Rtattr::new(None, IflaInfo::Kind, KIND_CAN)?)?;
+            link_info.add_nested_attribute(&data)?;
+
+            let mut rtattrs = RtBuffer::new();
+            rtattrs.push(link_info);
+            rtattrs
+        });
+        self.send_info_msg(Rtm::Newlink, info, &[])
+            .map_err(|err| match err.errno() {
+                Some(libc::EOPNOTSUPP) => CanNlError::NotSupported { param: param_name },
+                _ => err,
+            })
     }
 
     /// Set a CAN-specific set of parameters.
@@ -708,20 +2568,39 @@ impl CanInterface {
             }
 
             let mut link_info = Rtattr::new(None, Ifla::Linkinfo, Buffer::new())?;
-            link_info.add_nested_attribute(&Rtattr::new(None, IflaInfo::Kind, "can")?)?;
+            link_info.add_nested_attribute(&Rtattr::new(None, IflaInfo::Kind, KIND_CAN)?)?;
             link_info.add_nested_attribute(&data)?;
 
             rtattrs.push(link_info);
             rtattrs
         });
         */
-        Self::send_info_msg(Rtm::Newlink, info, &[])
+        self.send_info_msg(Rtm::Newlink, info, &[])
     }
 
     /// Attempt to query an individual CAN parameter on the interface.
-    pub fn can_param<P>(&self, param: IflaCan) -> Result<Option<P>, NlInfoError>
+    pub fn can_param<P>(&self, param: IflaCan) -> NlResult<Option<P>>
     where
         P: for<'a> FromBytes<'a> + Clone,
+    {
+        self.find_can_attr(param, |attr| Ok(attr.get_payload_as::<P>()?))
+    }
+
+    /// Attempt to query the raw bytes of an individual CAN parameter on
+    /// the interface.
+    ///
+    /// This is the payload of the attribute exactly as the kernel sent
+    /// it, for parameters whose layout isn't known, so that they can be
+    /// decoded separately.
+    pub fn can_param_raw(&self, param: IflaCan) -> NlResult<Option<Vec<u8>>> {
+        self.find_can_attr(param, |attr| Ok(attr.rta_payload.as_ref().to_vec()))
+    }
+
+    /// Queries the interface and runs `f` on the CAN parameter attribute,
+    /// if it was reported.
+    fn find_can_attr<F, R>(&self, param: IflaCan, f: F) -> NlResult<Option<R>>
+    where
+        F: FnOnce(&Rtattr<IflaCan, Buffer>) -> NlResult<R>,
     {
         if let Some(hdr) = self.query_details()? {
             if let Ok(payload) = hdr.get_payload() {
@@ -731,7 +2610,7 @@ impl CanInterface {
                             if info.rta_type == IflaInfo::Data {
                                 for attr in info.get_attr_handle::<IflaCan>()?.get_attrs() {
                                     if attr.rta_type == param {
-                                        return Ok(Some(attr.get_payload_as::<P>()?));
+                                        return Ok(Some(f(attr)?));
                                     }
                                 }
                             }
@@ -741,32 +2620,48 @@ impl CanInterface {
             }
             Ok(None)
         } else {
-            Err(NlError::NoAck)
+            Err(CanNlError::NoMessage)
         }
     }
 
     /// Gets the current bit rate for the interface.
-    pub fn bit_rate(&self) -> Result<Option<u32>, NlInfoError> {
+    pub fn bit_rate(&self) -> NlResult<Option<u32>> {
         Ok(self.bit_timing()?.map(|timing| timing.bitrate))
     }
 
+    /// Gets the fastest bitrate that the interface can be set to, in bits
+    /// per second.
+    ///
+    /// This is the maximum bitrate reported by the transceiver, if there
+    /// is one, and otherwise the classic CAN limit,
+    /// [`MAX_CLASSIC_BITRATE`](CanInterface::MAX_CLASSIC_BITRATE).
+    pub fn bitrate_max(&self) -> NlResult<u32> {
+        Ok(self
+            .can_param::<u32>(IflaCan::BitRateMax)?
+            .unwrap_or(Self::MAX_CLASSIC_BITRATE))
+    }
+
     /// Set the bitrate and, optionally, sample point of this interface.
     ///
     /// The bitrate can *not* be changed if the interface is UP. It is
-    /// specified in Hz (bps) while the sample point is given in tenths
-    /// of a percent/
+    /// specified in Hz (bps) while the sample point is given as a
+    /// [`SamplePoint`], such as `SamplePoint::from_percent(87.5)`.
+    ///
+    /// The kernel checks the bitrate against the limits of the hardware.
+    /// See [bitrate_max](CanInterface::bitrate_max).
     ///
     /// PRIVILEGED: This requires root privilege.
     ///
     pub fn set_bitrate<P>(&self, bitrate: u32, sample_point: P) -> NlResult<()>
     where
-        P: Into<Option<u32>>,
+        P: Into<Option<SamplePoint>>,
     {
-        let sample_point: u32 = sample_point.into().unwrap_or(0);
+        let sample_point = sample_point.into().map(u32::from).unwrap_or(0);
 
         debug_assert!(
-            0 < bitrate && bitrate <= 1000000,
-            "Bitrate must be within 1..=1000000, received {}.",
+            bitrate >= Self::MIN_BITRATE,
+            "Bitrate must be at least {}, received {}.",
+            Self::MIN_BITRATE,
             bitrate
         );
         debug_assert!(
@@ -783,7 +2678,12 @@ impl CanInterface {
     }
 
     /// Gets the bit timing params for the interface
-    pub fn bit_timing(&self) -> Result<Option<CanBitTiming>, NlInfoError> {
+    ///
+    /// The kernel fills in all of the fields, whether the timing was set
+    /// from a bitrate or from the segment lengths, including the length of
+    /// the time quantum (`tq`), in nanoseconds. See [`BitTiming`] for
+    /// named accessors.
+    pub fn bit_timing(&self) -> NlResult<Option<CanBitTiming>> {
         self.can_param::<CanBitTiming>(IflaCan::BitTiming)
     }
 
@@ -791,29 +2691,56 @@ impl CanInterface {
     ///
     /// PRIVILEGED: This requires root privilege.
     ///
-    pub fn set_bit_timing(&self, timing: CanBitTiming) -> NlResult<()> {
-        self.set_can_param(IflaCan::BitTiming, timing)
+    pub fn set_bit_timing<T>(&self, timing: T) -> NlResult<()>
+    where
+        T: Into<CanBitTiming>,
+    {
+        self.set_can_param(IflaCan::BitTiming, timing.into())
     }
 
     /// Gets the bit timing const data for the interface
-    pub fn bit_timing_const(&self) -> Result<Option<CanBitTimingConst>, NlInfoError> {
+    pub fn bit_timing_const(&self) -> NlResult<Option<CanBitTimingConst>> {
         self.can_param::<CanBitTimingConst>(IflaCan::BitTimingConst)
     }
 
     /// Gets the clock frequency for the interface
-    pub fn clock(&self) -> Result<Option<u32>, NlInfoError> {
+    pub fn clock(&self) -> NlResult<Option<u32>> {
         Ok(self
             .can_param::<CanClock>(IflaCan::Clock)?
             .map(|clk| clk.freq))
     }
 
+    /// Gets the clock frequency for the interface, in MHz.
+    ///
+    /// Controller datasheets give the clock in MHz, such as 40 or 80 MHz,
+    /// so this saves the conversion from the raw value, in Hz, returned
+    /// by [clock](CanInterface::clock).
+    pub fn clock_mhz(&self) -> NlResult<Option<f64>> {
+        Ok(self.clock()?.map(|freq| freq as f64 / 1_000_000.0))
+    }
+
     /// Gets the state of the interface
-    pub fn state(&self) -> Result<Option<CanState>, NlInfoError> {
+    pub fn state(&self) -> NlResult<Option<CanState>> {
         Ok(self
             .can_param::<u32>(IflaCan::State)?
             .and_then(|st| CanState::try_from(st).ok()))
     }
 
+    /// Waits for the interface to reach the specified CAN state.
+    ///
+    /// This polls the state of the interface, backing off between queries,
+    /// until it reaches the `target` state or the `timeout` expires. It
+    /// returns whether the state was reached in time. This is useful when
+    /// bringing up an interface and waiting for it to recover from
+    /// bus-off, for example.
+    pub fn wait_for_state(&self, target: CanState, timeout: Duration) -> NlResult<bool> {
+        let reached = poll_until(
+            timeout,
+            || Ok((self.state()? == Some(target)).then_some(())),
+        )?;
+        Ok(reached.is_some())
+    }
+
     /// Set the full control mode (bit) collection.
     ///
     /// PRIVILEGED: This requires root privilege.
@@ -844,87 +2771,526 @@ impl CanInterface {
         self.set_ctrlmodes(CanCtrlModes::from_mode(mode, on))
     }
 
-    /// Gets the automatic CANbus restart time for the interface, in milliseconds.
-    pub fn restart_ms(&self) -> Result<Option<u32>, NlInfoError> {
-        self.can_param::<u32>(IflaCan::RestartMs)
-    }
-
-    /// Set the automatic restart milliseconds of the interface
+    /// Set or clear a number of control mode parameters at once.
+    ///
+    /// The modes are combined and sent to the kernel in a single message,
+    /// so either all of them are applied or none are. Any modes not in
+    /// the list are left unchanged.
     ///
     /// PRIVILEGED: This requires root privilege.
     ///
-    pub fn set_restart_ms(&self, restart_ms: u32) -> NlResult<()> {
-        self.set_can_param(IflaCan::RestartMs, &restart_ms.to_ne_bytes()[..])
+    pub fn set_ctrlmode_list(&self, modes: &[(CanCtrlMode, bool)]) -> NlResult<()> {
+        self.set_ctrlmodes(modes.iter().copied().collect::<CanCtrlModes>())
     }
 
-    /// Manually restart the interface.
+    /// Gets the control modes that are currently set on the interface.
+    pub fn ctrlmodes(&self) -> NlResult<Option<CanCtrlModes>> {
+        Ok(self
+            .can_param::<can_ctrlmode>(IflaCan::CtrlMode)?
+            .map(CanCtrlModes::from))
+    }
+
+    /// Turn off all of the known control modes in a single call.
     ///
-    /// Note that a manual restart if only permitted if automatic restart is
-    /// disabled and the device is in the bus-off state.
-    /// See: linux/drivers/net/can/dev/dev.c
+    /// This sends a mask covering every mode in [`CanCtrlMode::ALL`] with
+    /// all of the flags cleared, which is useful to return an interface to
+    /// a known baseline before applying a fresh set of modes.
     ///
     /// PRIVILEGED: This requires root privilege.
     ///
-    /// Common Errors:
-    ///     EINVAL - The interface is down or automatic restarts are enabled
-    ///     EBUSY - The interface is not in a bus-off state
-    ///
-    pub fn restart(&self) -> NlResult<()> {
-        // Note: The linux code shows the data type to be u32, but never
-        // appears to access the value sent. iproute2 sends a 1, so we do
-        // too!
-        // See: linux/drivers/net/can/dev/netlink.c
-        let restart_data: u32 = 1;
-        self.set_can_param(IflaCan::Restart, &restart_data.to_ne_bytes()[..])
-    }
-
-    /// Gets the bus error counter from the interface
-    pub fn berr_counter(&self) -> Result<Option<CanBerrCounter>, NlInfoError> {
-        self.can_param::<CanBerrCounter>(IflaCan::BerrCounter)
+    pub fn reset_ctrlmodes(&self) -> NlResult<()> {
+        let mut modes = CanCtrlModes::default();
+        for mode in CanCtrlMode::ALL {
+            modes.add(mode, false);
+        }
+        self.set_ctrlmodes(modes)
     }
 
-    /// Gets the data bit timing params for the interface
-    pub fn data_bit_timing(&self) -> Result<Option<CanBitTiming>, NlInfoError> {
-        self.can_param::<CanBitTiming>(IflaCan::DataBitTiming)
+    /// Set or clear listen-only mode.
+    ///
+    /// In listen-only mode the controller passively monitors the bus. It
+    /// never transmits, and does not even acknowledge received frames,
+    /// which makes it a safe way to sniff an unknown bus.
+    ///
+    /// The kernel only accepts control mode changes while the interface is
+    /// down, so this should be bracketed by calls to
+    /// [bring_down](CanInterface::bring_down) and
+    /// [bring_up](CanInterface::bring_up).
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn set_listen_only(&self, on: bool) -> NlResult<()> {
+        self.set_ctrlmode(CanCtrlMode::ListenOnly, on)
     }
 
-    /// Sets the data bit timing params for the interface
+    /// Set or clear loopback mode.
+    ///
+    /// As with other control modes, the interface must be down to change
+    /// this.
     ///
     /// PRIVILEGED: This requires root privilege.
     ///
-    pub fn set_data_bit_timing(&self, timing: CanBitTiming) -> NlResult<()> {
-        self.set_can_param(IflaCan::DataBitTiming, timing)
+    pub fn set_loopback(&self, on: bool) -> NlResult<()> {
+        self.set_ctrlmode(CanCtrlMode::Loopback, on)
     }
 
-    /// Set the data bitrate and, optionally, data sample point of this
+    /// Checks that frames can actually be sent and received on the
     /// interface.
     ///
-    /// This only applies to interfaces in FD mode.
+    /// This sends a frame through a raw CAN socket and waits for it to
+    /// come back, up to the netlink timeout of the interface. For a
+    /// hardware controller, loopback mode is turned on for the test, so
+    /// that the controller acknowledges its own frame without any other
+    /// nodes on the bus, then turned back off. That requires briefly
+    /// bringing the interface down. Virtual interfaces echo frames
+    /// themselves, so are left as they are.
     ///
-    /// The data bitrate can *not* be changed if the interface is UP. It is
-    /// specified in Hz (bps) while the sample point is given in tenths
-    /// of a percent/
+    /// This returns `false` if the frame didn't come back in time. The
+    /// interface should be up before calling this.
     ///
-    /// PRIVILEGED: This requires root privilege.
+    /// PRIVILEGED: This requires root privilege for hardware interfaces.
     ///
-    pub fn set_data_bitrate<P>(&self, bitrate: u32, sample_point: P) -> NlResult<()>
-    where
-        P: Into<Option<u32>>,
-    {
-        let sample_point: u32 = sample_point.into().unwrap_or(0);
+    pub fn self_test(&self) -> NlResult<bool> {
+        let set_loopback = !self.is_virtual()? && !self.has_ctrlmode(CanCtrlMode::Loopback)?;
 
-        self.set_data_bit_timing(CanBitTiming {
-            bitrate,
-            sample_point,
-            ..CanBitTiming::default()
-        })
+        if !set_loopback {
+            return self.loopback_probe();
+        }
+
+        self.bring_down()?;
+        let res = self
+            .set_loopback(true)
+            .and_then(|()| self.bring_up())
+            .and_then(|()| self.loopback_probe());
+
+        // Attempt every restore step, whatever happened above, so that
+        // the interface is left up and out of loopback mode if possible.
+        let restore = [self.bring_down(), self.set_loopback(false), self.bring_up()];
+        let ok = res?;
+        restore.into_iter().collect::<NlResult<()>>()?;
+        Ok(ok)
+    }
+
+    /// Sends a test frame on the interface and waits for it to be looped
+    /// back.
+    fn loopback_probe(&self) -> NlResult<bool> {
+        let frame = CanFrame::new(StandardId::MAX, &[0x55, 0xAA, 0x55, 0xAA])
+            .ok_or_else(|| CanNlError::Netlink(NlError::Msg("Invalid test frame".into())))?;
+
+        let sock = CanSocket::open_iface(self.if_index)?;
+        sock.set_recv_own_msgs(true)?;
+        sock.write_frame(&frame)?;
+
+        let deadline = Instant::now() + self.timeout.unwrap_or(DEFAULT_NL_TIMEOUT);
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match sock.read_frame_timeout(remaining) {
+                Ok(rx) if rx.id() == frame.id() && rx.data() == frame.data() => return Ok(true),
+                Ok(_) => (),
+                Err(err) if err.kind() == io::ErrorKind::TimedOut => return Ok(false),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Set or clear triple sampling mode.
+    ///
+    /// In this mode the controller samples each bit three times and takes
+    /// the majority value, which helps on slow, noisy buses.
+    ///
+    /// As with other control modes, the interface must be down to change
+    /// this.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn set_triple_sampling(&self, on: bool) -> NlResult<()> {
+        self.set_ctrlmode(CanCtrlMode::TripleSampling, on)
+    }
+
+    /// Determines if the interface is in triple sampling mode.
+    ///
+    /// It is `false` if the interface doesn't report its modes.
+    pub fn is_triple_sampling(&self) -> NlResult<bool> {
+        self.has_ctrlmode(CanCtrlMode::TripleSampling)
+    }
+
+    /// Set or clear CAN FD mode.
+    ///
+    /// As with other control modes, the interface must be down to change
+    /// this.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn set_fd(&self, on: bool) -> NlResult<()> {
+        self.set_ctrlmode(CanCtrlMode::Fd, on)
+    }
+
+    /// Set or clear the classic CAN "length 8 DLC" mode.
+    ///
+    /// A Classic CAN frame can only carry up to 8 bytes of data, but the
+    /// 4-bit DLC field on the wire can hold values of 9 to 15. Some legacy
+    /// buses abuse this by sending frames with a DLC greater than 8 (but
+    /// still with 8 data bytes). When this mode is on, the controller
+    /// preserves these raw DLC values, and they are passed to and from
+    /// applications in the `len8_dlc` field of the kernel's `can_frame`,
+    /// rather than being clamped to 8.
+    ///
+    /// As with other control modes, the interface must be down to change
+    /// this.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn set_len8_dlc(&self, on: bool) -> NlResult<()> {
+        self.set_ctrlmode(CanCtrlMode::CcLen8Dlc, on)
+    }
+
+    /// Set or clear bus-error reporting.
+    ///
+    /// When this is on, the controller reports each bus error, such as a
+    /// bit or stuff error, as an error frame, and counts it in the bus
+    /// error counters. This gives visibility into a marginal bus, like an
+    /// FD bus that sees occasional bit errors in the data phase, but can
+    /// generate a lot of interrupts on a noisy bus.
+    ///
+    /// As with other control modes, the interface must be down to change
+    /// this.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn set_berr_reporting(&self, on: bool) -> NlResult<()> {
+        self.set_ctrlmode(CanCtrlMode::BerrReporting, on)
+    }
+
+    /// Determines if bus-error reporting is enabled on the interface.
+    ///
+    /// This is `None` if the interface doesn't report its control modes.
+    pub fn is_berr_reporting(&self) -> NlResult<Option<bool>> {
+        Ok(self
+            .ctrlmodes()?
+            .map(|modes| modes.has_mode(CanCtrlMode::BerrReporting)))
+    }
+
+    /// Turns the presume-ACK mode on or off.
+    ///
+    /// In this mode the controller ignores missing ACKs, so that it can
+    /// transmit on a bus with no other nodes, such as when bench-testing a
+    /// lone ECU.
+    ///
+    /// As with other control modes, the interface must be down to change
+    /// this.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn set_presume_ack(&self, on: bool) -> NlResult<()> {
+        self.set_ctrlmode(CanCtrlMode::PresumeAck, on)
+    }
+
+    /// Determines if the interface is in the presume-ACK mode.
+    ///
+    /// It is `false` if the interface doesn't report its modes.
+    pub fn is_presume_ack(&self) -> NlResult<bool> {
+        self.has_ctrlmode(CanCtrlMode::PresumeAck)
+    }
+
+    /// Determines if the interface is in the non-ISO CAN FD mode.
+    ///
+    /// This is the original Bosch CAN FD protocol, from before the ISO
+    /// 11898-1:2015 standard added a stuff bit counter to the frame. The
+    /// two are not compatible on the same bus, so this is a common cause
+    /// of errors when mixing newer controllers with older transceivers or
+    /// ECUs. It is `false` if the interface doesn't report its modes.
+    pub fn is_non_iso(&self) -> NlResult<bool> {
+        self.has_ctrlmode(CanCtrlMode::NonIso)
+    }
+
+    /// Determines if the control mode is set on the interface, treating
+    /// modes that aren't reported as off.
+    fn has_ctrlmode(&self, mode: CanCtrlMode) -> NlResult<bool> {
+        Ok(self
+            .ctrlmodes()?
+            .map(|modes| modes.has_mode(mode))
+            .unwrap_or(false))
+    }
+
+    /// Gets the automatic CANbus restart time for the interface, in milliseconds.
+    pub fn restart_ms(&self) -> NlResult<Option<u32>> {
+        self.can_param::<u32>(IflaCan::RestartMs)
+    }
+
+    /// Set the automatic restart milliseconds of the interface
+    ///
+    /// A value of zero disables automatic restart. For clarity, consider
+    /// using [enable_auto_restart](CanInterface::enable_auto_restart) or
+    /// [disable_auto_restart](CanInterface::disable_auto_restart) instead.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn set_restart_ms(&self, restart_ms: u32) -> NlResult<()> {
+        self.set_can_param(IflaCan::RestartMs, &restart_ms.to_ne_bytes()[..])
+    }
+
+    /// Enables automatic restart of the interface after a bus-off.
+    ///
+    /// The controller is restarted `restart_ms` milliseconds after going
+    /// bus-off. The time must be non-zero, since zero would disable the
+    /// automatic restart.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn enable_auto_restart(&self, restart_ms: u32) -> NlResult<()> {
+        if restart_ms == 0 {
+            return Err(CanNlError::Netlink(NlError::Msg(
+                "The auto-restart time must be non-zero".into(),
+            )));
+        }
+        self.set_restart_ms(restart_ms)
+    }
+
+    /// Disables automatic restart of the interface after a bus-off.
+    ///
+    /// The interface then stays bus-off until it is restarted manually,
+    /// with [restart](CanInterface::restart).
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn disable_auto_restart(&self) -> NlResult<()> {
+        self.set_restart_ms(0)
+    }
+
+    /// Determines if automatic restart is enabled on the interface.
+    ///
+    /// This is derived from the restart time, where a value of zero means
+    /// that automatic restart is disabled. It is `None` if the interface
+    /// doesn't report a restart time.
+    pub fn auto_restart_enabled(&self) -> NlResult<Option<bool>> {
+        Ok(self.restart_ms()?.map(|ms| ms > 0))
+    }
+
+    /// Manually restart the interface.
+    ///
+    /// Note that a manual restart if only permitted if automatic restart is
+    /// disabled and the device is in the bus-off state.
+    /// See: linux/drivers/net/can/dev/dev.c
+    ///
+    /// This checks for automatic restart first, and returns a descriptive
+    /// error if it is enabled, rather than the kernel's bare EINVAL.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    /// Common Errors:
+    ///     [`CanNlError::AutoRestartEnabled`] - Automatic restarts are enabled
+    ///     [`CanNlError::NotBusOff`] - The interface is not in a bus-off state
+    ///     EINVAL - The interface is down
+    ///
+    pub fn restart(&self) -> NlResult<()> {
+        if self.auto_restart_enabled()? == Some(true) {
+            return Err(CanNlError::AutoRestartEnabled);
+        }
+
+        // Note: The linux code shows the data type to be u32, but never
+        // appears to access the value sent. iproute2 sends a 1, so we do
+        // too!
+        // See: linux/drivers/net/can/dev/netlink.c
+        let restart_data: u32 = 1;
+        self.set_can_param(IflaCan::Restart, &restart_data.to_ne_bytes()[..])
+            .map_err(|err| match err.errno() {
+                Some(libc::EBUSY) => CanNlError::NotBusOff,
+                _ => err,
+            })
+    }
+
+    /// Gets the bus error counter from the interface
+    pub fn berr_counter(&self) -> NlResult<Option<CanBerrCounter>> {
+        self.can_param::<CanBerrCounter>(IflaCan::BerrCounter)
+    }
+
+    /// Gets the data bit timing params for the interface
+    pub fn data_bit_timing(&self) -> NlResult<Option<CanBitTiming>> {
+        self.can_param::<CanBitTiming>(IflaCan::DataBitTiming)
+    }
+
+    /// Sets the data bit timing params for the interface
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn set_data_bit_timing<T>(&self, timing: T) -> NlResult<()>
+    where
+        T: Into<CanBitTiming>,
+    {
+        self.set_can_param(IflaCan::DataBitTiming, timing.into())
+    }
+
+    /// Set the data bitrate and, optionally, data sample point of this
+    /// interface.
+    ///
+    /// This only applies to interfaces in FD mode.
+    ///
+    /// The data bitrate can *not* be changed if the interface is UP. It is
+    /// specified in Hz (bps) while the sample point is given as a
+    /// [`SamplePoint`], such as `SamplePoint::from_percent(75.0)`.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn set_data_bitrate<P>(&self, bitrate: u32, sample_point: P) -> NlResult<()>
+    where
+        P: Into<Option<SamplePoint>>,
+    {
+        let sample_point = sample_point.into().map(u32::from).unwrap_or(0);
+
+        self.set_data_bit_timing(CanBitTiming {
+            bitrate,
+            sample_point,
+            ..CanBitTiming::default()
+        })
+    }
+
+    /// Set the data sample point of this interface, keeping the current
+    /// data bitrate.
+    ///
+    /// This reads the data bitrate back from the interface and requests it
+    /// again with the new sample point, letting the kernel recalculate the
+    /// data phase segments. It fails if no data bitrate has been set.
+    ///
+    /// The interface must be down when this is called.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn set_data_sample_point(&self, sample_point: SamplePoint) -> NlResult<()> {
+        let bitrate = self
+            .data_bit_timing()?
+            .map(|timing| timing.bitrate)
+            .filter(|&bitrate| bitrate != 0)
+            .ok_or_else(|| {
+                CanNlError::Netlink(NlError::Msg(
+                    "The interface has no data bitrate to keep".into(),
+                ))
+            })?;
+        self.set_data_bitrate(bitrate, sample_point)
+    }
+
+    /// Switch the interface to CAN FD operation.
+    ///
+    /// This turns on the FD control mode, raises the MTU to [`Mtu::Fd`],
+    /// and sets the data bitrate and, optionally, the data sample point.
+    ///
+    /// The order matters: the kernel rejects the FD MTU and the data bit
+    /// timing unless the FD control mode is already enabled, so these are
+    /// sent as separate messages in that sequence.
+    ///
+    /// The interface must be down when this is called.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn enable_fd<P>(&self, data_bitrate: u32, data_sample_point: P) -> NlResult<()>
+    where
+        P: Into<Option<SamplePoint>>,
+    {
+        self.set_fd(true)?;
+        self.set_mtu(Mtu::Fd)?;
+        self.set_data_bitrate(data_bitrate, data_sample_point)
+    }
+
+    /// Switch the interface back to classic CAN operation.
+    ///
+    /// This is the reverse of [enable_fd](CanInterface::enable_fd). It
+    /// lowers the MTU to [`Mtu::Standard`] and then turns off the FD
+    /// control mode, which also makes the kernel discard the data bit
+    /// timing. Lowering the MTU first means the interface is never left in
+    /// classic mode with the FD MTU, even if the second step fails.
+    ///
+    /// The interface must be down when this is called.
+    ///
+    /// PRIVILEGED: This requires root privilege.
+    ///
+    pub fn disable_fd(&self) -> NlResult<()> {
+        self.set_mtu(Mtu::Standard)?;
+        self.set_fd(false)
     }
 
     /// Gets the data bit timing const params for the interface
-    pub fn data_bit_timing_const(&self) -> Result<Option<CanBitTimingConst>, NlInfoError> {
+    pub fn data_bit_timing_const(&self) -> NlResult<Option<CanBitTimingConst>> {
         self.can_param::<CanBitTimingConst>(IflaCan::DataBitTimingConst)
     }
 
+    /// Determines if the interface can run at a bitrate, to within a
+    /// tolerance, given in parts per million.
+    ///
+    /// This runs the bit timing calculation, with
+    /// [`calc_bit_timing`], for the CAN clock and bit timing limits of the
+    /// interface, and checks how close the resulting bitrate comes to the
+    /// requested one. This lets a configuration UI rule out a bitrate
+    /// before the kernel rejects it.
+    ///
+    /// It is `false` if the interface doesn't report its clock and bit
+    /// timing limits, as is the case for virtual interfaces and for
+    /// controllers that only support a fixed set of bitrates.
+    pub fn can_achieve_bitrate(&self, bitrate: u32, tolerance_ppm: u32) -> NlResult<bool> {
+        let params = self.can_params()?;
+        let actual = match (params.clock, params.bit_timing_const) {
+            (Some(clock), Some(btc)) => calc_bit_timing(clock.freq, &btc, bitrate, None)
+                .map(|timing| BitTiming::from(timing).bitrate_for_clock(clock.freq)),
+            _ => None,
+        };
+        Ok(actual.is_some_and(|actual| {
+            let err = u64::from(actual.abs_diff(bitrate));
+            err * 1_000_000 <= u64::from(tolerance_ppm) * u64::from(bitrate)
+        }))
+    }
+
+    /// Gets the range of arbitration bitrates that the interface supports,
+    /// as the slowest and fastest, in bits per second.
+    ///
+    /// This is derived from the bit timing limits and the CAN clock
+    /// frequency, and is further capped by the maximum bitrate of the
+    /// transceiver, if the interface reports one. It is `None` if the
+    /// interface doesn't report its bit timing limits or its clock.
+    ///
+    /// Not every bitrate within the range can necessarily be reached
+    /// exactly. See [can_achieve_bitrate](CanInterface::can_achieve_bitrate)
+    /// to check a specific one.
+    pub fn supported_bitrate_range(&self) -> NlResult<Option<(u32, u32)>> {
+        let params = self.can_params()?;
+        let range = match (params.clock, params.bit_timing_const) {
+            (Some(clock), Some(btc)) => timing::min_bitrate(clock.freq, &btc)
+                .zip(timing::max_bitrate(clock.freq, &btc))
+                .map(|(min, max)| (min, params.bitrate_max.map_or(max, |lim| max.min(lim)))),
+            _ => None,
+        };
+        Ok(range)
+    }
+
+    /// Gets the fastest data bitrate that the interface supports for FD.
+    ///
+    /// The kernel doesn't report a maximum for the data phase, so this is
+    /// derived from the data bit timing limits, using the smallest
+    /// prescaler and the shortest bit time at the CAN clock frequency.
+    /// This is `None` if the interface doesn't report its data bit timing
+    /// limits or its clock, as is the case for classic controllers.
+    ///
+    /// The transceiver may limit the usable bitrate further.
+    pub fn data_bitrate_max(&self) -> NlResult<Option<u32>> {
+        let params = self.can_params()?;
+        let max = match (params.clock, params.data_bit_timing_const) {
+            (Some(clock), Some(btc)) => timing::max_bitrate(clock.freq, &btc),
+            _ => None,
+        };
+        Ok(max)
+    }
+
+    /// Determines if the interface supports CAN FD.
+    ///
+    /// This checks whether the driver reports the limits for the FD data
+    /// phase bit timing, which only FD-capable controllers have. It can
+    /// be used to check the hardware before attempting to
+    /// [enable FD](CanInterface::enable_fd) on the interface.
+    ///
+    /// Note that virtual (vcan) interfaces report no bit timing at all,
+    /// and so are not considered FD-capable by this check, even though
+    /// they can carry FD frames.
+    pub fn supports_fd(&self) -> NlResult<bool> {
+        Ok(self.details()?.can.data_bit_timing_const.is_some())
+    }
+
     /// Sets the CANbus termination for the interface
     ///
     /// Not all interfaces support setting a termination.
@@ -937,13 +3303,346 @@ impl CanInterface {
         self.set_can_param(IflaCan::Termination, termination)
     }
 
-    /// Gets the CANbus termination for the interface
-    pub fn termination(&self) -> Result<Option<u16>, NlInfoError> {
-        self.can_param::<u16>(IflaCan::Termination)
+    /// Gets the CANbus termination for the interface
+    pub fn termination(&self) -> NlResult<Option<u16>> {
+        self.can_param::<u16>(IflaCan::Termination)
+    }
+}
+
+/// A guard that brings an interface down when it goes out of scope.
+///
+/// This is created by [CanInterface::bring_up_scoped].
+#[derive(Debug)]
+pub struct InterfaceUpGuard<'a> {
+    iface: &'a CanInterface,
+}
+
+impl Drop for InterfaceUpGuard<'_> {
+    fn drop(&mut self) {
+        // There's no way to report an error from a drop
+        let _ = self.iface.bring_down();
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod ctrlmode_tests {
+    use super::*;
+
+    // Note that virtual (vcan) interfaces don't accept CAN control modes,
+    // so these check the decoding of the modes as the kernel reports them,
+    // with the flags set and the mask cleared.
+
+    #[test]
+    fn decode_each_mode() {
+        // Each mode with its bit from the kernel's netlink header
+        let table = [
+            (CanCtrlMode::Loopback, rt::CAN_CTRLMODE_LOOPBACK),
+            (CanCtrlMode::ListenOnly, rt::CAN_CTRLMODE_LISTENONLY),
+            (CanCtrlMode::TripleSampling, rt::CAN_CTRLMODE_3_SAMPLES),
+            (CanCtrlMode::OneShot, rt::CAN_CTRLMODE_ONE_SHOT),
+            (CanCtrlMode::BerrReporting, rt::CAN_CTRLMODE_BERR_REPORTING),
+            (CanCtrlMode::Fd, rt::CAN_CTRLMODE_FD),
+            (CanCtrlMode::PresumeAck, rt::CAN_CTRLMODE_PRESUME_ACK),
+            (CanCtrlMode::NonIso, rt::CAN_CTRLMODE_FD_NON_ISO),
+            (CanCtrlMode::CcLen8Dlc, rt::CAN_CTRLMODE_CC_LEN8_DLC),
+        ];
+        assert_eq!(table.map(|(mode, _)| mode), CanCtrlMode::ALL);
+
+        for (mode, flag) in table {
+            // As read back from the kernel
+            let modes = CanCtrlModes::new(0, flag);
+            assert_eq!(modes.active_modes(), vec![mode]);
+            for other in CanCtrlMode::ALL {
+                assert_eq!(modes.has_mode(other), other == mode);
+            }
+
+            // Toggled on, then off again
+            let mut modes = CanCtrlModes::from_mode(mode, true);
+            assert!(modes.has_mode(mode));
+            modes.add(mode, false);
+            assert!(!modes.has_mode(mode));
+            assert_eq!(modes.masked_modes(), vec![mode]);
+        }
+    }
+
+    #[test]
+    fn masks() {
+        for mode in CanCtrlMode::ALL {
+            assert_eq!(mode.mask64(), u64::from(mode.mask()));
+        }
+        assert_eq!(CanCtrlMode::Fd.mask(), rt::CAN_CTRLMODE_FD);
+        assert_eq!(CanCtrlMode::CcLen8Dlc.mask(), rt::CAN_CTRLMODE_CC_LEN8_DLC);
+    }
+
+    #[test]
+    fn mode_list() {
+        let modes: CanCtrlModes = [
+            (CanCtrlMode::Fd, true),
+            (CanCtrlMode::Loopback, false),
+            (CanCtrlMode::ListenOnly, true),
+        ]
+        .into_iter()
+        .collect();
+
+        let raw = can_ctrlmode::from(modes);
+        assert_eq!(
+            raw.mask,
+            rt::CAN_CTRLMODE_FD | rt::CAN_CTRLMODE_LOOPBACK | rt::CAN_CTRLMODE_LISTENONLY
+        );
+        assert_eq!(raw.flags, rt::CAN_CTRLMODE_FD | rt::CAN_CTRLMODE_LISTENONLY);
+    }
+
+    #[test]
+    fn active_and_masked() {
+        let mut modes = CanCtrlModes::from_mode(CanCtrlMode::Fd, true);
+        modes.add(CanCtrlMode::Loopback, false);
+        assert_eq!(modes.active_modes(), vec![CanCtrlMode::Fd]);
+        assert_eq!(
+            modes.masked_modes(),
+            vec![CanCtrlMode::Loopback, CanCtrlMode::Fd]
+        );
+
+        // As read back from the kernel
+        let flags = rt::CAN_CTRLMODE_LISTENONLY | rt::CAN_CTRLMODE_FD;
+        let modes = CanCtrlModes::new(0, flags);
+        assert_eq!(
+            modes.active_modes(),
+            vec![CanCtrlMode::ListenOnly, CanCtrlMode::Fd]
+        );
+        assert!(modes.masked_modes().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod error_tests {
+    use super::*;
+
+    #[test]
+    fn restart_errors() {
+        assert_eq!(CanNlError::NotBusOff.errno(), Some(libc::EBUSY));
+        assert_eq!(CanNlError::AutoRestartEnabled.errno(), Some(libc::EINVAL));
+        assert!(!CanNlError::NotBusOff.is_transient());
+    }
+
+    #[test]
+    fn permission_denied() {
+        let err = CanNlError::from_errno(libc::EPERM);
+        assert!(matches!(err, CanNlError::PermissionDenied));
+        assert_eq!(err.errno(), Some(libc::EPERM));
+        assert!(err.to_string().contains("CAP_NET_ADMIN"));
+
+        let err = CanNlError::from_errno(libc::EBUSY);
+        assert!(matches!(err, CanNlError::Kernel { errno: libc::EBUSY }));
+    }
+
+    #[test]
+    fn restore_failed() {
+        let err = CanNlError::RestoreFailed {
+            error: Box::new(CanNlError::from_errno(libc::EINVAL)),
+            restore_error: Box::new(CanNlError::Timeout),
+        };
+        assert_eq!(err.errno(), Some(libc::EINVAL));
+        assert!(!err.is_transient());
+        assert!(err.to_string().contains("restoring the prior state"));
+    }
+
+    #[test]
+    fn config_errors() {
+        for err in [
+            CanNlError::StillUp,
+            CanNlError::NoClock,
+            CanNlError::TimingOutOfRange,
+        ] {
+            assert_eq!(err.errno(), None);
+            assert!(!err.is_transient());
+        }
+        assert!(CanNlError::NoClock.to_string().contains("no CAN clock"));
+    }
+}
+
+#[cfg(test)]
+mod msg_tests {
+    use super::*;
+
+    // These build and decode netlink messages without sending them to
+    // the kernel.
+
+    #[test]
+    fn bit_timing_tq() {
+        // 500kbps @ 87.5% on an 80MHz clock, as reported by the kernel:
+        // bitrate, sample_point, tq, prop_seg, phase_seg1, phase_seg2,
+        // sjw, brp
+        let fields: [u32; 8] = [500_000, 875, 125, 6, 7, 2, 1, 10];
+        let bytes: Vec<u8> = fields.iter().flat_map(|v| v.to_ne_bytes()).collect();
+
+        let mut data = Rtattr::new(None, IflaInfo::Data, Buffer::new()).unwrap();
+        data.add_nested_attribute(&Rtattr::new(None, IflaCan::BitTiming, &bytes[..]).unwrap())
+            .unwrap();
+        let mut link_info = Rtattr::new(None, Ifla::Linkinfo, Buffer::new()).unwrap();
+        link_info.add_nested_attribute(&data).unwrap();
+
+        let params = InterfaceCanParams::try_from(&link_info).unwrap();
+        let timing = BitTiming::from(params.bit_timing.unwrap());
+        assert_eq!(timing.bitrate(), 500_000);
+        assert_eq!(timing.tq(), 125);
+        assert_eq!(timing.brp(), 10);
+        assert_eq!(timing.sample_point_percent(), 87.5);
+    }
+
+    #[test]
+    fn details_from_nlmsg() {
+        let info = Ifinfomsg::new(
+            RtAddrFamily::Unspecified,
+            Arphrd::Netrom,
+            7,
+            IffFlags::new(&[Iff::Up]),
+            IffFlags::empty(),
+            {
+                let mut buffer = RtBuffer::new();
+                buffer.push(Rtattr::new(None, Ifla::Ifname, "can7").unwrap());
+                buffer.push(Rtattr::new(None, Ifla::Mtu, 72u32).unwrap());
+                buffer
+            },
+        );
+        let msg = Nlmsghdr::new(
+            None,
+            Rtm::Newlink,
+            NlmFFlags::empty(),
+            None,
+            None,
+            NlPayload::Payload(info),
+        );
+
+        let details = InterfaceDetails::from_nlmsg(&msg).unwrap();
+        assert_eq!(details.index, 7);
+        assert!(details.is_up);
+        assert_eq!(details.name_lossy().as_deref(), Some("can7"));
+        assert_eq!(details.mtu, Some(Mtu::Fd));
+    }
+
+    #[test]
+    fn index_mismatch() {
+        let info = Ifinfomsg::new(
+            RtAddrFamily::Unspecified,
+            Arphrd::Netrom,
+            7,
+            IffFlags::empty(),
+            IffFlags::empty(),
+            RtBuffer::new(),
+        );
+        let msg = Nlmsghdr::new(
+            None,
+            Rtm::Newlink,
+            NlmFFlags::empty(),
+            None,
+            None,
+            NlPayload::Payload(info),
+        );
+
+        match CanInterface::open_iface(42).details_from_msg(Some(msg)) {
+            Err(CanNlError::IndexMismatch { expected, actual }) => {
+                assert_eq!(expected, 42);
+                assert_eq!(actual, 7);
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn dry_run() {
+        let iface = CanInterface::dry_run(42);
+        assert!(iface.is_dry_run());
+
+        iface.set_bitrate(500_000, None).unwrap();
+        iface.bring_up().unwrap();
+
+        let msgs = iface.take_dry_run_msgs();
+        assert_eq!(msgs.len(), 2);
+        assert!(msgs.iter().all(|msg| msg.nl_type == Rtm::Newlink));
+
+        let info = msgs[0].get_payload().unwrap();
+        assert_eq!(info.ifi_index, 42);
+        assert!(info
+            .rtattrs
+            .iter()
+            .any(|attr| attr.rta_type == Ifla::Linkinfo));
+
+        let info = msgs[1].get_payload().unwrap();
+        assert!(info.ifi_flags.contains(&Iff::Up));
+
+        assert!(iface.take_dry_run_msgs().is_empty());
+        assert!(CanInterface::open_iface(42).take_dry_run_msgs().is_empty());
+    }
+
+    #[test]
+    fn dry_run_without_queries() {
+        // None of these can reach the kernel, since there's no interface
+        // with this index
+        let iface = CanInterface::dry_run(u32::MAX);
+
+        iface
+            .reconfigure(|iface| iface.set_bitrate(250_000, None))
+            .unwrap();
+        let msgs = iface.take_dry_run_msgs();
+        assert_eq!(msgs.len(), 3);
+        assert!(!msgs[0].get_payload().unwrap().ifi_flags.contains(&Iff::Up));
+        assert!(msgs[2].get_payload().unwrap().ifi_flags.contains(&Iff::Up));
+
+        let config = CanInterfaceConfig::default().bitrate(500_000).mtu(Mtu::Fd);
+        iface.apply_config_with_restart(config).unwrap();
+        let msgs = iface.take_dry_run_msgs();
+        assert_eq!(msgs.len(), 3);
+
+        // Assumed to be a CAN controller, so FD mode goes with the FD MTU
+        let info = msgs[1].get_payload().unwrap();
+        let link_info = info
+            .rtattrs
+            .iter()
+            .find(|attr| attr.rta_type == Ifla::Linkinfo)
+            .unwrap();
+        let params = InterfaceCanParams::try_from(link_info).unwrap();
+        assert!(params.ctrl_mode.unwrap().has_mode(CanCtrlMode::Fd));
+    }
+}
+
+#[cfg(test)]
+mod iface_tests {
+    use super::*;
+
+    #[test]
+    fn index() {
+        assert_eq!(CanInterface::open_iface(42).index(), 42);
+        assert_eq!(CanInterface::dry_run(7).index(), 7);
+    }
+
+    #[test]
+    fn iface_eq_hash() {
+        use std::{
+            collections::hash_map::DefaultHasher,
+            hash::{Hash, Hasher},
+        };
+
+        let hash = |iface: &CanInterface| {
+            let mut hasher = DefaultHasher::new();
+            iface.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let mut iface = CanInterface::open_iface(42);
+        iface.set_timeout(None);
+        assert_eq!(iface, CanInterface::open_iface(42));
+        assert_ne!(iface, CanInterface::open_iface(43));
+        assert_eq!(hash(&iface), hash(&CanInterface::open_iface(42)));
     }
-}
 
-/////////////////////////////////////////////////////////////////////////////
+    #[test]
+    fn mtu_requires_fd_mode() {
+        assert!(Mtu::Fd.requires_fd_mode());
+        assert!(!Mtu::Standard.requires_fd_mode());
+    }
+}
 
 #[cfg(feature = "netlink_tests")]
 #[cfg(test)]
@@ -976,16 +3675,14 @@ pub mod tests {
         #[allow(unused)]
         pub fn new(name: &str) -> NlResult<Self> {
             Ok(Self {
-                interface: CanInterface::create_vcan(name, None)?,
+                interface: CanInterface::recreate_vcan(name)?,
             })
         }
     }
 
     impl Drop for TemporaryInterface {
         fn drop(&mut self) {
-            assert!(CanInterface::open_iface(self.interface.if_index)
-                .delete()
-                .is_ok());
+            assert!(self.interface.delete_ref().is_ok());
         }
     }
 
@@ -1009,6 +3706,131 @@ pub mod tests {
         assert!(!interface.details().unwrap().is_up);
     }
 
+    #[test]
+    #[serial]
+    fn cached_socket() {
+        let interface = TemporaryInterface::new("cached_socket").unwrap();
+        let mut cached = CanInterface::open_iface(interface.if_index);
+        cached.set_cache_socket(true);
+
+        assert!(cached.bring_up().is_ok());
+        assert!(cached.details().unwrap().is_up);
+        assert!(cached.sock.lock().unwrap().is_some());
+
+        // A second socket can't take the PID, so it gets its own port.
+        let port = cached.netlink_port().unwrap().unwrap();
+        assert_ne!(port, 0);
+        assert!(CanInterface::list().is_ok());
+
+        assert!(cached.bring_down().is_ok());
+        assert!(!cached.details().unwrap().is_up);
+
+        cached.set_cache_socket(false);
+        assert!(cached.sock.lock().unwrap().is_none());
+        assert_eq!(cached.netlink_port().unwrap(), None);
+    }
+
+    #[test]
+    #[serial]
+    fn bring_down_for_reconfig() {
+        let interface = TemporaryInterface::new("down_reconfig").unwrap();
+        interface.bring_up().unwrap();
+        interface.bring_down_for_reconfig().unwrap();
+        assert!(!interface.details().unwrap().is_up);
+    }
+
+    #[test]
+    #[serial]
+    fn strict_check() {
+        let interface = TemporaryInterface::new("strict_check").unwrap();
+        let mut strict = CanInterface::open_iface(interface.if_index);
+        assert!(!strict.strict_check());
+
+        strict.set_strict_check(true);
+        assert!(strict.strict_check());
+        assert!(strict.bring_up().is_ok());
+        assert!(strict.details().unwrap().is_up);
+    }
+
+    #[test]
+    #[serial]
+    fn self_test() {
+        let interface = TemporaryInterface::new("self_test").unwrap();
+        interface.bring_up().unwrap();
+        assert!(interface.self_test().unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn bind_pid() {
+        let interface = TemporaryInterface::new("bind_pid").unwrap();
+        let mut unbound = CanInterface::open_iface(interface.if_index);
+        assert!(unbound.bind_pid());
+
+        unbound.set_bind_pid(false);
+        unbound.set_cache_socket(true);
+        assert!(!unbound.bind_pid());
+        assert!(unbound.details().is_ok());
+        assert!(unbound.netlink_port().unwrap().is_some());
+    }
+
+    #[test]
+    #[serial]
+    fn apply_config_with_restart() {
+        let interface = TemporaryInterface::new("apply_restart").unwrap();
+
+        let config = CanInterfaceConfig::default().mtu(Mtu::Fd);
+        interface.apply_config_with_restart(config).unwrap();
+        let details = interface.details().unwrap();
+        assert!(details.is_up);
+        assert_eq!(details.mtu, Some(Mtu::Fd));
+
+        // A virtual interface rejects CAN parameters, so this fails and
+        // leaves the interface as it was.
+        let config = CanInterfaceConfig::default()
+            .mtu(Mtu::Standard)
+            .bitrate(500_000);
+        assert!(interface.apply_config_with_restart(config).is_err());
+        let details = interface.details().unwrap();
+        assert!(details.is_up);
+        assert_eq!(details.mtu, Some(Mtu::Fd));
+    }
+
+    #[test]
+    #[serial]
+    fn reconfigure() {
+        let interface = TemporaryInterface::new("reconfigure").unwrap();
+        interface.bring_up().unwrap();
+
+        interface
+            .reconfigure(|iface| {
+                assert!(!iface.details()?.is_up);
+                iface.set_mtu(Mtu::Fd)
+            })
+            .unwrap();
+
+        let details = interface.details().unwrap();
+        assert!(details.is_up);
+        assert_eq!(details.mtu, Some(Mtu::Fd));
+
+        // The state is restored even when the closure fails
+        let res = interface.reconfigure(|_| Err(CanNlError::Timeout));
+        assert!(matches!(res, Err(CanNlError::Timeout)));
+        assert!(interface.details().unwrap().is_up);
+    }
+
+    #[test]
+    #[serial]
+    fn up_scoped() {
+        let interface = TemporaryInterface::new("up_scoped").unwrap();
+
+        {
+            let _guard = interface.bring_up_scoped().unwrap();
+            assert!(interface.details().unwrap().is_up);
+        }
+        assert!(!interface.details().unwrap().is_up);
+    }
+
     #[test]
     #[serial]
     fn details() {
@@ -1019,6 +3841,458 @@ pub mod tests {
         assert!(!details.is_up);
     }
 
+    #[test]
+    #[serial]
+    fn oper_state() {
+        let interface = TemporaryInterface::new("oper_state").unwrap();
+        assert_eq!(interface.oper_state().unwrap(), Some(OperState::Down));
+
+        // Virtual interfaces don't track a carrier, so report "unknown"
+        interface.bring_up().unwrap();
+        assert_eq!(interface.oper_state().unwrap(), Some(OperState::Unknown));
+    }
+
+    #[test]
+    #[serial]
+    fn wait_for_state() {
+        let interface = TemporaryInterface::new("wait_state").unwrap();
+
+        // Virtual interfaces don't report a CAN state, so this should time out
+        let timeout = Duration::from_millis(50);
+        let start = Instant::now();
+        assert!(!interface
+            .wait_for_state(CanState::ErrorActive, timeout)
+            .unwrap());
+        assert!(start.elapsed() >= timeout);
+    }
+
+    #[test]
+    #[serial]
+    fn len8_dlc() {
+        let interface = TemporaryInterface::new("len8_dlc").unwrap();
+
+        // Virtual interfaces have no control modes
+        assert_eq!(interface.ctrlmodes().unwrap(), None);
+        let err = interface.set_len8_dlc(true).unwrap_err();
+        assert!(matches!(
+            err,
+            CanNlError::NotSupported {
+                param: "control mode"
+            }
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn berr_reporting() {
+        let interface = TemporaryInterface::new("berr_reporting").unwrap();
+
+        // Virtual interfaces have no control modes
+        assert_eq!(interface.is_berr_reporting().unwrap(), None);
+        let err = interface.set_berr_reporting(true).unwrap_err();
+        assert!(matches!(
+            err,
+            CanNlError::NotSupported {
+                param: "control mode"
+            }
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn non_iso() {
+        let interface = TemporaryInterface::new("non_iso").unwrap();
+
+        // Virtual interfaces have no control modes
+        assert!(!interface.is_non_iso().unwrap());
+        let err = interface
+            .set_ctrlmode(CanCtrlMode::NonIso, true)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CanNlError::NotSupported {
+                param: "control mode"
+            }
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn presume_ack() {
+        let interface = TemporaryInterface::new("presume_ack").unwrap();
+
+        // Virtual interfaces have no control modes
+        assert!(!interface.is_presume_ack().unwrap());
+        let err = interface.set_presume_ack(true).unwrap_err();
+        assert!(matches!(
+            err,
+            CanNlError::NotSupported {
+                param: "control mode"
+            }
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn triple_sampling() {
+        let interface = TemporaryInterface::new("triple_sampling").unwrap();
+
+        // Virtual interfaces have no control modes
+        assert!(!interface.is_triple_sampling().unwrap());
+        let err = interface.set_triple_sampling(true).unwrap_err();
+        assert!(matches!(
+            err,
+            CanNlError::NotSupported {
+                param: "control mode"
+            }
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn build_for_no_clock() {
+        let interface = TemporaryInterface::new("no_clock").unwrap();
+
+        // Virtual interfaces have no CAN clock
+        let builder = BitTimingBuilder::new()
+            .brp(10)
+            .prop_seg(6)
+            .phase_seg1(7)
+            .phase_seg2(2)
+            .sjw(1);
+        let err = builder.build_for(&interface).unwrap_err();
+        assert!(matches!(err, CanNlError::NoClock));
+    }
+
+    #[cfg(any(feature = "async-io", feature = "async-std"))]
+    #[test]
+    #[serial]
+    fn async_settings() {
+        use ::async_io::block_on;
+
+        let interface = TemporaryInterface::new("async_settings").unwrap();
+
+        let mut iface = CanInterface::open_iface(interface.if_index);
+        iface.set_timeout(Duration::from_millis(500));
+        iface.set_bind_pid(false);
+        iface.set_strict_check(true);
+        let iface = AsyncCanInterface::from(iface);
+        block_on(iface.bring_up()).unwrap();
+        assert!(block_on(iface.details()).unwrap().is_up);
+
+        // Requests in dry-run mode are recorded, not sent
+        let iface = AsyncCanInterface::from(CanInterface::dry_run(interface.if_index));
+        block_on(iface.bring_down()).unwrap();
+        assert_eq!(iface.inner().take_dry_run_msgs().len(), 1);
+        assert!(interface.details().unwrap().is_up);
+    }
+
+    #[test]
+    #[serial]
+    fn not_supported() {
+        let interface = TemporaryInterface::new("not_supported").unwrap();
+
+        // Virtual interfaces don't take any CAN parameters
+        let err = interface.set_data_bitrate(2_000_000, None).unwrap_err();
+        assert!(matches!(
+            err,
+            CanNlError::NotSupported {
+                param: "FD data bit timing"
+            }
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn alias() {
+        let interface = TemporaryInterface::new("alias").unwrap();
+        assert_eq!(interface.alias().unwrap(), None);
+
+        interface.set_alias("powertrain").unwrap();
+        assert_eq!(interface.alias().unwrap().as_deref(), Some("powertrain"));
+
+        interface.set_alias("").unwrap();
+        assert_eq!(interface.alias().unwrap(), None);
+    }
+
+    #[test]
+    #[serial]
+    fn can_param_raw() {
+        // A virtual interface has no CAN parameters
+        let interface = TemporaryInterface::new("param_raw").unwrap();
+        assert_eq!(interface.can_param_raw(IflaCan::BitTiming).unwrap(), None);
+    }
+
+    #[test]
+    #[serial]
+    fn group() {
+        let interface = TemporaryInterface::new("group").unwrap();
+        assert_eq!(interface.group().unwrap(), Some(0));
+
+        interface.set_group(7).unwrap();
+        assert_eq!(interface.group().unwrap(), Some(7));
+    }
+
+    #[test]
+    #[serial]
+    fn delete_matching() {
+        CanInterface::create_vcan("dm_test_a", None).unwrap();
+        CanInterface::create_vcan("dm_test_b", None).unwrap();
+        let other = TemporaryInterface::new("dm_other").unwrap();
+
+        assert_eq!(CanInterface::delete_matching("dm_test_").unwrap(), 2);
+        assert!(CanInterface::open("dm_test_a").is_err());
+        assert!(CanInterface::open("dm_test_b").is_err());
+        assert!(other.details().is_ok());
+
+        assert_eq!(CanInterface::delete_matching("dm_test_").unwrap(), 0);
+    }
+
+    #[test]
+    #[serial]
+    fn wait_for() {
+        let timeout = Duration::from_millis(50);
+        assert!(matches!(
+            CanInterface::wait_for("wait_for", timeout),
+            Err(CanNlError::Timeout)
+        ));
+
+        let handle = thread::spawn(|| {
+            thread::sleep(Duration::from_millis(100));
+            CanInterface::create_vcan("wait_for", None).unwrap()
+        });
+        let interface = CanInterface::wait_for("wait_for", Duration::from_secs(5)).unwrap();
+        assert_eq!(interface, handle.join().unwrap());
+        interface.delete().unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn create_vcan_up() {
+        let interface = CanInterface::create_vcan_up("vcan_up", None).unwrap();
+        assert!(interface.details().unwrap().is_up);
+        interface.delete().unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn create_vxcan_pair() {
+        let interface = CanInterface::create_vxcan_pair("vxcan_a", "vxcan_b", None).unwrap();
+        let peer = CanInterface::open("vxcan_b").unwrap();
+
+        assert_eq!(interface.kind().unwrap().as_deref(), Some("vxcan"));
+        assert_eq!(interface.peer_index().unwrap(), Some(peer.if_index));
+
+        // Deleting one half of the pair deletes the other
+        interface.delete().unwrap();
+        assert!(CanInterface::open("vxcan_b").is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn recreate_vcan() {
+        let interface = CanInterface::create_vcan("recreate", None).unwrap();
+        interface.set_alias("stale").unwrap();
+
+        let interface = CanInterface::recreate_vcan("recreate").unwrap();
+        assert_eq!(interface.alias().unwrap(), None);
+        interface.delete().unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn kind() {
+        let interface = TemporaryInterface::new("kind").unwrap();
+        assert_eq!(interface.kind().unwrap().as_deref(), Some("vcan"));
+        assert!(interface.is_virtual().unwrap());
+        assert_eq!(
+            interface.link_info().unwrap(),
+            Some(("vcan".to_string(), None))
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn qdisc() {
+        let interface = TemporaryInterface::new("qdisc").unwrap();
+        interface.bring_up().unwrap();
+        assert!(interface.qdisc().unwrap().is_some());
+    }
+
+    #[test]
+    #[serial]
+    fn flags() {
+        let interface = TemporaryInterface::new("flags").unwrap();
+
+        let flags = interface.flags().unwrap();
+        assert!(flags.contains(&Iff::Noarp));
+        assert!(!flags.contains(&Iff::Up));
+
+        interface.bring_up().unwrap();
+        let flags = interface.flags().unwrap();
+        assert!(flags.contains(&Iff::Up));
+        assert!(flags.contains(&Iff::Running));
+    }
+
+    #[test]
+    #[serial]
+    fn can_params() {
+        let interface = TemporaryInterface::new("can_params").unwrap();
+
+        // Virtual interfaces have no CAN parameters
+        let params = interface.can_params().unwrap();
+        assert_eq!(params, InterfaceCanParams::default());
+    }
+
+    #[test]
+    #[serial]
+    fn master() {
+        let interface = TemporaryInterface::new("master").unwrap();
+        assert_eq!(interface.master().unwrap(), None);
+        assert_eq!(interface.details().unwrap().master, None);
+    }
+
+    #[test]
+    #[serial]
+    fn carrier_changes() {
+        let interface = TemporaryInterface::new("carrier_chg").unwrap();
+        let before = interface.carrier_changes().unwrap().unwrap_or(0);
+
+        interface.bring_up().unwrap();
+        interface.bring_down().unwrap();
+
+        if let Some(after) = interface.carrier_changes().unwrap() {
+            assert!(after >= before);
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn statistics() {
+        let interface = TemporaryInterface::new("statistics").unwrap();
+
+        let earlier = interface.statistics().unwrap().unwrap();
+        let later = interface.statistics().unwrap().unwrap();
+        let delta = later.delta(&earlier);
+        assert!(!delta.reset);
+        assert!(later.time >= earlier.time);
+    }
+
+    #[test]
+    #[serial]
+    fn supports_fd() {
+        let interface = TemporaryInterface::new("supports_fd").unwrap();
+        assert!(!interface.supports_fd().unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn bitrate_max() {
+        let interface = TemporaryInterface::new("bitrate_max").unwrap();
+        assert_eq!(
+            interface.bitrate_max().unwrap(),
+            CanInterface::MAX_CLASSIC_BITRATE
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn supported_bitrate_range() {
+        let interface = TemporaryInterface::new("br_range").unwrap();
+        assert_eq!(interface.supported_bitrate_range().unwrap(), None);
+    }
+
+    #[test]
+    #[serial]
+    fn can_achieve_bitrate() {
+        // A virtual interface has no clock or bit timing limits
+        let interface = TemporaryInterface::new("achieve_br").unwrap();
+        assert!(!interface.can_achieve_bitrate(500_000, 1000).unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn open_with_details() {
+        let interface = TemporaryInterface::new("open_details").unwrap();
+
+        let (iface, details) = CanInterface::open_with_details("open_details").unwrap();
+        assert_eq!(iface.if_index, interface.if_index);
+        assert_eq!(details.index, interface.if_index);
+        assert_eq!(details.name_lossy().as_deref(), Some("open_details"));
+
+        assert!(CanInterface::open_with_details("no_such_iface").is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn details_query() {
+        let interface = TemporaryInterface::new("query").unwrap();
+
+        let details = InterfaceDetails::query("query").unwrap();
+        assert_eq!(details.index, interface.if_index);
+        assert_eq!(details.kind.as_deref(), Some("vcan"));
+
+        let err = InterfaceDetails::query("no_such_iface").unwrap_err();
+        assert_eq!(err.errno(), Some(libc::ENODEV));
+    }
+
+    #[test]
+    #[serial]
+    fn list() {
+        let interface = TemporaryInterface::new("list").unwrap();
+        let ifaces = CanInterface::list().unwrap();
+        assert!(ifaces
+            .iter()
+            .any(|details| details.index == interface.if_index
+                && details.name.as_deref() == Some(OsStr::new("list"))));
+    }
+
+    #[test]
+    #[serial]
+    fn list_names() {
+        let _interface = TemporaryInterface::new("list_names").unwrap();
+        let names = CanInterface::list_names().unwrap();
+        assert!(names.iter().any(|name| name == "list_names"));
+    }
+
+    #[test]
+    #[serial]
+    fn name() {
+        let interface = TemporaryInterface::new("name").unwrap();
+        assert_eq!("name", interface.name().unwrap().unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn create_vcan_fd() {
+        let interface = CanInterface::create_vcan_fd("vcan_fd", None).unwrap();
+        let mtu = interface.details().map(|details| details.mtu);
+        assert!(interface.delete_ref().is_ok());
+        assert_eq!(mtu.unwrap(), Some(Mtu::Fd));
+    }
+
+    #[test]
+    #[serial]
+    fn create_builder() {
+        let interface = CreateBuilder::new("create_bld", "vcan")
+            .mtu(Mtu::Fd)
+            .txqueuelen(100)
+            .num_tx_queues(4)
+            .num_rx_queues(2)
+            .create()
+            .unwrap()
+            .unwrap();
+        let txqueuelen = interface.link_param::<u32>(Ifla::Txqlen);
+        let num_tx_queues = interface.link_param::<u32>(Ifla::NumTxQueues);
+        let num_rx_queues = interface.link_param::<u32>(Ifla::NumRxQueues);
+        let mtu = interface.details().map(|details| details.mtu);
+        assert!(interface.delete_ref().is_ok());
+        assert_eq!(txqueuelen.unwrap(), Some(100));
+        assert_eq!(num_tx_queues.unwrap(), Some(4));
+        assert_eq!(num_rx_queues.unwrap(), Some(2));
+        assert_eq!(mtu.unwrap(), Some(Mtu::Fd));
+    }
+
     #[test]
     #[serial]
     fn mtu() {
@@ -1029,5 +4303,11 @@ pub mod tests {
 
         assert!(interface.set_mtu(Mtu::Standard).is_ok());
         assert_eq!(Mtu::Standard, interface.details().unwrap().mtu.unwrap());
+
+        assert!(interface.set_raw_mtu(72).is_ok());
+        assert_eq!(Mtu::Fd, interface.details().unwrap().mtu.unwrap());
+
+        let err = interface.set_raw_mtu(20).unwrap_err();
+        assert_eq!(err.errno(), Some(libc::EINVAL));
     }
 }
@@ -19,7 +19,7 @@
 #![allow(non_camel_case_types, unused)]
 
 use crate::{as_bytes, as_bytes_mut};
-use libc::{c_char, c_uint};
+use libc::{c_char, c_int, c_uint};
 use neli::{
     consts::rtnl::{RtaType, RtaTypeWrapper},
     err::{DeError, SerError},
@@ -31,15 +31,24 @@ use std::{
     mem::size_of,
 };
 
+/// Report SR-IOV virtual function info
 pub const EXT_FILTER_VF: c_uint = 1 << 0;
+/// Report bridge VLAN info
 pub const EXT_FILTER_BRVLAN: c_uint = 1 << 1;
+/// Report bridge VLAN info, compressed into ranges
 pub const EXT_FILTER_BRVLAN_COMPRESSED: c_uint = 1 << 2;
+/// Skip the link statistics
 pub const EXT_FILTER_SKIP_STATS: c_uint = 1 << 3;
 pub const EXT_FILTER_MRP: c_uint = 1 << 4;
 pub const EXT_FILTER_CFM_CONFIG: c_uint = 1 << 5;
 pub const EXT_FILTER_CFM_STATUS: c_uint = 1 << 6;
 pub const EXT_FILTER_MST: c_uint = 1 << 7;
 
+/// The socket option level for netlink sockets
+pub const SOL_NETLINK: c_int = 270;
+/// Socket option to have rtnetlink strictly check requests
+pub const NETLINK_GET_STRICT_CHK: c_int = 12;
+
 /// CAN bit-timing parameters
 ///
 /// For further information, please read chapter "8 BIT TIMING
@@ -47,7 +56,8 @@ pub const EXT_FILTER_MST: c_uint = 1 << 7;
 /// at http://www.semiconductors.bosch.de/pdf/can2spec.pdf.
 ///
 #[repr(C)]
-#[derive(Debug, Default, Clone, Copy, FromBytes, ToBytes, Size)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, FromBytes, ToBytes, Size)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct can_bittiming {
     pub bitrate: u32,      // Bit-rate in bits/second
     pub sample_point: u32, // Sample point in one-tenth of a percent
@@ -65,7 +75,8 @@ pub struct can_bittiming {
 /// Used for calculating and checking bit-timing parameters
 ///
 #[repr(C)]
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct can_bittiming_const {
     pub name: [c_char; 16], // Name of the CAN controller hardware
     pub tseg1_min: u32,     // Time segment 1 = prop_seg + phase_seg1
@@ -102,7 +113,8 @@ impl Size for can_bittiming_const {
 /// CAN clock parameters
 ///
 #[repr(C)]
-#[derive(Debug, Default, Clone, Copy, FromBytes, ToBytes, Size)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, FromBytes, ToBytes, Size)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct can_clock {
     pub freq: u32, // CAN system clock frequency in Hz
 }
@@ -111,6 +123,7 @@ pub struct can_clock {
 ///
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CanState {
     /// RX/TX error count < 96
     ErrorActive,
@@ -147,7 +160,8 @@ impl TryFrom<u32> for CanState {
 /// CAN bus error counters
 ///
 #[repr(C)]
-#[derive(Debug, Default, Copy, Clone, FromBytes, ToBytes, Size)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, FromBytes, ToBytes, Size)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct can_berr_counter {
     pub txerr: u16,
     pub rxerr: u16,
@@ -159,7 +173,8 @@ pub struct can_berr_counter {
 /// the bit in the `flags` and send via `set_ctrlmode()`.
 ///
 #[repr(C)]
-#[derive(Debug, Default, Copy, Clone, FromBytes, ToBytes, Size)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, FromBytes, ToBytes, Size)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct can_ctrlmode {
     pub mask: u32,
     pub flags: u32,
@@ -246,6 +261,20 @@ pub enum IflaCan {
 
 impl RtaType for IflaCan {}
 
+// vxcan link info data attributes
+pub const VXCAN_INFO_UNSPEC: u16 = 0;
+pub const VXCAN_INFO_PEER: u16 = 1;
+
+/// vxcan netlink link info data
+///
+#[neli_enum(serialized_type = "libc::c_ushort")]
+pub enum VxcanInfo {
+    Unspec = VXCAN_INFO_UNSPEC,
+    Peer = VXCAN_INFO_PEER,
+}
+
+impl RtaType for VxcanInfo {}
+
 /////////////////////////////////////////////////////////////////////////////
 
 #[cfg(test)]
@@ -272,4 +301,24 @@ pub mod tests {
             as_bytes(&timing)
         );
     }
+
+    #[test]
+    fn test_can_state() {
+        use CanState::*;
+
+        let states = [
+            ErrorActive,
+            ErrorWarning,
+            ErrorPassive,
+            BusOff,
+            Stopped,
+            Sleeping,
+        ];
+        for state in states {
+            assert_eq!(CanState::try_from(state as u32).unwrap(), state);
+        }
+
+        let err = CanState::try_from(6).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
 }
@@ -0,0 +1,221 @@
+// socketcan/src/nl/stats.rs
+//
+// Interface traffic statistics.
+//
+// This file is part of the Rust 'socketcan-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+
+//! Interface traffic statistics.
+//!
+//! The kernel reports cumulative counters for each link. A
+//! [`StatsSnapshot`] captures them along with the time that they were
+//! read, so that two snapshots can be compared to get the traffic rates
+//! over the interval between them.
+
+use std::time::{Duration, Instant};
+
+/// A snapshot of the traffic counters of an interface.
+///
+/// This is read from the kernel's 64-bit link statistics
+/// (`IFLA_STATS64`) by [statistics](super::CanInterface::statistics).
+/// For a CAN interface, the packet counters are frame counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatsSnapshot {
+    /// The time at which the counters were read.
+    pub time: Instant,
+    /// The number of frames received.
+    pub rx_packets: u64,
+    /// The number of frames transmitted.
+    pub tx_packets: u64,
+    /// The number of bytes received.
+    pub rx_bytes: u64,
+    /// The number of bytes transmitted.
+    pub tx_bytes: u64,
+    /// The number of receive errors.
+    pub rx_errors: u64,
+    /// The number of transmit errors.
+    pub tx_errors: u64,
+    /// The number of received frames that were dropped.
+    pub rx_dropped: u64,
+    /// The number of outgoing frames that were dropped.
+    pub tx_dropped: u64,
+}
+
+impl StatsSnapshot {
+    /// Creates a snapshot from the raw payload of an `IFLA_STATS64`
+    /// attribute, taken at the specified time.
+    ///
+    /// The kernel struct, `rtnl_link_stats64`, has grown over time, so
+    /// only the leading counters are read, and any that are missing from
+    /// a short payload are left at zero.
+    pub(super) fn from_raw(time: Instant, buf: &[u8]) -> Self {
+        let field = |n: usize| {
+            buf.get(n * 8..(n + 1) * 8)
+                .map(|b| u64::from_ne_bytes(b.try_into().unwrap()))
+                .unwrap_or_default()
+        };
+        Self {
+            time,
+            rx_packets: field(0),
+            tx_packets: field(1),
+            rx_bytes: field(2),
+            tx_bytes: field(3),
+            rx_errors: field(4),
+            tx_errors: field(5),
+            rx_dropped: field(6),
+            tx_dropped: field(7),
+        }
+    }
+
+    /// Gets the change in the counters since an earlier snapshot.
+    ///
+    /// If any counter went backwards, the interface was probably
+    /// recreated, and its counters restarted from zero, in the interval.
+    /// In that case, the delta is taken from zero for all of the counters
+    /// and [`StatsDelta::reset`] is set.
+    pub fn delta(&self, earlier: &StatsSnapshot) -> StatsDelta {
+        let reset = [
+            (self.rx_packets, earlier.rx_packets),
+            (self.tx_packets, earlier.tx_packets),
+            (self.rx_bytes, earlier.rx_bytes),
+            (self.tx_bytes, earlier.tx_bytes),
+            (self.rx_errors, earlier.rx_errors),
+            (self.tx_errors, earlier.tx_errors),
+            (self.rx_dropped, earlier.rx_dropped),
+            (self.tx_dropped, earlier.tx_dropped),
+        ]
+        .iter()
+        .any(|(now, then)| now < then);
+
+        let diff = |now: u64, then: u64| if reset { now } else { now - then };
+
+        StatsDelta {
+            elapsed: self.time.saturating_duration_since(earlier.time),
+            reset,
+            rx_packets: diff(self.rx_packets, earlier.rx_packets),
+            tx_packets: diff(self.tx_packets, earlier.tx_packets),
+            rx_bytes: diff(self.rx_bytes, earlier.rx_bytes),
+            tx_bytes: diff(self.tx_bytes, earlier.tx_bytes),
+            rx_errors: diff(self.rx_errors, earlier.rx_errors),
+            tx_errors: diff(self.tx_errors, earlier.tx_errors),
+            rx_dropped: diff(self.rx_dropped, earlier.rx_dropped),
+            tx_dropped: diff(self.tx_dropped, earlier.tx_dropped),
+        }
+    }
+}
+
+/// The change in the traffic counters of an interface between two
+/// snapshots.
+///
+/// This is returned by [`StatsSnapshot::delta`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StatsDelta {
+    /// The time between the two snapshots.
+    pub elapsed: Duration,
+    /// Whether the counters were reset between the two snapshots.
+    pub reset: bool,
+    /// The number of frames received.
+    pub rx_packets: u64,
+    /// The number of frames transmitted.
+    pub tx_packets: u64,
+    /// The number of bytes received.
+    pub rx_bytes: u64,
+    /// The number of bytes transmitted.
+    pub tx_bytes: u64,
+    /// The number of receive errors.
+    pub rx_errors: u64,
+    /// The number of transmit errors.
+    pub tx_errors: u64,
+    /// The number of received frames that were dropped.
+    pub rx_dropped: u64,
+    /// The number of outgoing frames that were dropped.
+    pub tx_dropped: u64,
+}
+
+impl StatsDelta {
+    /// Converts a count over the interval into a rate per second.
+    fn rate(&self, count: u64) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            count as f64 / secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Gets the rate of received frames, in frames per second.
+    pub fn rx_frames_per_sec(&self) -> f64 {
+        self.rate(self.rx_packets)
+    }
+
+    /// Gets the rate of transmitted frames, in frames per second.
+    pub fn tx_frames_per_sec(&self) -> f64 {
+        self.rate(self.tx_packets)
+    }
+
+    /// Gets the rate of received data, in bytes per second.
+    pub fn rx_bytes_per_sec(&self) -> f64 {
+        self.rate(self.rx_bytes)
+    }
+
+    /// Gets the rate of transmitted data, in bytes per second.
+    pub fn tx_bytes_per_sec(&self) -> f64 {
+        self.rate(self.tx_bytes)
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(time: Instant, rx_packets: u64, rx_bytes: u64) -> StatsSnapshot {
+        let mut buf = [0u8; 64];
+        buf[0..8].copy_from_slice(&rx_packets.to_ne_bytes());
+        buf[16..24].copy_from_slice(&rx_bytes.to_ne_bytes());
+        StatsSnapshot::from_raw(time, &buf)
+    }
+
+    #[test]
+    fn test_from_raw_short() {
+        let snap = StatsSnapshot::from_raw(Instant::now(), &7u64.to_ne_bytes());
+        assert_eq!(snap.rx_packets, 7);
+        assert_eq!(snap.tx_packets, 0);
+        assert_eq!(snap.tx_dropped, 0);
+    }
+
+    #[test]
+    fn test_delta() {
+        let start = Instant::now();
+        let earlier = snapshot(start, 100, 800);
+        let later = snapshot(start + Duration::from_secs(2), 300, 2400);
+
+        let delta = later.delta(&earlier);
+        assert!(!delta.reset);
+        assert_eq!(delta.elapsed, Duration::from_secs(2));
+        assert_eq!(delta.rx_packets, 200);
+        assert_eq!(delta.rx_frames_per_sec(), 100.0);
+        assert_eq!(delta.rx_bytes_per_sec(), 800.0);
+        assert_eq!(delta.tx_frames_per_sec(), 0.0);
+    }
+
+    #[test]
+    fn test_delta_reset() {
+        let start = Instant::now();
+        let earlier = snapshot(start, 100, 800);
+        let later = snapshot(start + Duration::from_secs(1), 40, 320);
+
+        let delta = later.delta(&earlier);
+        assert!(delta.reset);
+        assert_eq!(delta.rx_packets, 40);
+        assert_eq!(delta.rx_bytes, 320);
+
+        // No time elapsed
+        assert_eq!(earlier.delta(&earlier).rx_frames_per_sec(), 0.0);
+    }
+}
@@ -0,0 +1,781 @@
+// socketcan/src/nl/timing.rs
+//
+// Higher-level CAN bit timing types.
+//
+// This file is part of the Rust 'socketcan-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+
+//! Higher-level CAN bit timing types.
+//!
+//! These wrap the raw kernel bit timing structs with named accessors and
+//! unit conversions, so that applications don't need to know the layout
+//! or units of the underlying netlink data.
+
+use super::{CanBitTiming, CanBitTimingConst, CanInterface, CanNlError, NlResult};
+use std::fmt;
+
+/// CAN bit timing parameters.
+///
+/// This is a wrapper around the raw kernel bit timing struct,
+/// [`CanBitTiming`], as returned by
+/// [bit_timing](super::CanInterface::bit_timing) and
+/// [data_bit_timing](super::CanInterface::data_bit_timing).
+///
+/// The segment lengths and the synchronisation jump width are all given
+/// in time quanta (TQ).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct BitTiming(CanBitTiming);
+
+impl BitTiming {
+    /// Gets the bitrate, in bits per second.
+    pub fn bitrate(&self) -> u32 {
+        self.0.bitrate
+    }
+
+    /// Gets the sample point as a percentage of the bit time.
+    ///
+    /// The kernel reports this in tenths of a percent, so a value of
+    /// 875 is returned here as 87.5.
+    pub fn sample_point(&self) -> f32 {
+        self.0.sample_point as f32 / 10.0
+    }
+
+    /// Computes the actual sample point from the segment lengths, as a
+    /// percentage of the bit time.
+    ///
+    /// The sample point that can be achieved with whole time quanta might
+    /// differ a little from the one that was requested, so this gives the
+    /// real one. It is zero if the segment lengths are not known.
+    pub fn sample_point_percent(&self) -> f32 {
+        let tseg1 = 1 + self.0.prop_seg + self.0.phase_seg1;
+        let nbt = tseg1 + self.0.phase_seg2;
+        if self.0.phase_seg2 == 0 {
+            return 0.0;
+        }
+        100.0 * tseg1 as f32 / nbt as f32
+    }
+
+    /// Gets the length of a time quantum (TQ), in nanoseconds.
+    pub fn tq(&self) -> u32 {
+        self.0.tq
+    }
+
+    /// Computes the length of a time quantum (TQ), in nanoseconds, from
+    /// the CAN `clock` frequency (in Hz) and the bitrate prescaler.
+    ///
+    /// Unlike [tq](BitTiming::tq), which the kernel rounds to whole
+    /// nanoseconds, this is exact. It is zero if the clock is zero.
+    pub fn tq_ns(&self, clock: u32) -> f64 {
+        if clock == 0 {
+            return 0.0;
+        }
+        1e9 * self.0.brp as f64 / clock as f64
+    }
+
+    /// Computes the bitrate, in bits per second, from the CAN `clock`
+    /// frequency (in Hz), the bitrate prescaler, and the segment lengths.
+    ///
+    /// Unlike [bitrate](BitTiming::bitrate), this doesn't rely on the
+    /// bitrate field being filled in, which it isn't for timing that is
+    /// built to be sent to the kernel.
+    pub fn bitrate_for_clock(&self, clock: u32) -> u32 {
+        let nbt = 1 + self.0.prop_seg + self.0.phase_seg1 + self.0.phase_seg2;
+        clock / (self.0.brp.max(1) * nbt)
+    }
+
+    /// Gets the nominal bit time, in nanoseconds.
+    ///
+    /// This is the time on the wire for a single bit at the bitrate, or
+    /// zero if the bitrate is not known. See [`bit_time_ns`].
+    pub fn bit_time_ns(&self) -> f64 {
+        bit_time_ns(self.0.bitrate)
+    }
+
+    /// Gets the propagation segment length, in TQ.
+    pub fn prop_seg(&self) -> u32 {
+        self.0.prop_seg
+    }
+
+    /// Gets the phase buffer segment 1 length, in TQ.
+    pub fn phase_seg1(&self) -> u32 {
+        self.0.phase_seg1
+    }
+
+    /// Gets the phase buffer segment 2 length, in TQ.
+    pub fn phase_seg2(&self) -> u32 {
+        self.0.phase_seg2
+    }
+
+    /// Gets the synchronisation jump width, in TQ.
+    pub fn sjw(&self) -> u32 {
+        self.0.sjw
+    }
+
+    /// Gets the bitrate prescaler.
+    pub fn brp(&self) -> u32 {
+        self.0.brp
+    }
+
+    /// Gets the raw kernel bit timing struct.
+    pub fn into_inner(self) -> CanBitTiming {
+        self.0
+    }
+}
+
+impl From<CanBitTiming> for BitTiming {
+    fn from(timing: CanBitTiming) -> Self {
+        Self(timing)
+    }
+}
+
+impl From<BitTiming> for CanBitTiming {
+    fn from(timing: BitTiming) -> Self {
+        timing.0
+    }
+}
+
+/// The limits of the bit timing parameters of a CAN controller.
+///
+/// This is a wrapper around the raw kernel bit timing constants struct,
+/// [`CanBitTimingConst`], as returned by
+/// [bit_timing_const](super::CanInterface::bit_timing_const) and
+/// [data_bit_timing_const](super::CanInterface::data_bit_timing_const).
+///
+/// The segment lengths and the synchronisation jump width are all given
+/// in time quanta (TQ).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct BitTimingLimits(CanBitTimingConst);
+
+impl BitTimingLimits {
+    /// Gets the name of the CAN controller hardware.
+    pub fn name(&self) -> String {
+        let name: Vec<u8> = self
+            .0
+            .name
+            .iter()
+            .take_while(|&&c| c != 0)
+            .map(|&c| c as u8)
+            .collect();
+        String::from_utf8_lossy(&name).into_owned()
+    }
+
+    /// Gets the minimum length of time segment 1 (`prop_seg + phase_seg1`),
+    /// in TQ.
+    pub fn tseg1_min(&self) -> u32 {
+        self.0.tseg1_min
+    }
+
+    /// Gets the maximum length of time segment 1 (`prop_seg + phase_seg1`),
+    /// in TQ.
+    pub fn tseg1_max(&self) -> u32 {
+        self.0.tseg1_max
+    }
+
+    /// Gets the minimum length of time segment 2 (`phase_seg2`), in TQ.
+    pub fn tseg2_min(&self) -> u32 {
+        self.0.tseg2_min
+    }
+
+    /// Gets the maximum length of time segment 2 (`phase_seg2`), in TQ.
+    pub fn tseg2_max(&self) -> u32 {
+        self.0.tseg2_max
+    }
+
+    /// Gets the maximum synchronisation jump width, in TQ.
+    pub fn sjw_max(&self) -> u32 {
+        self.0.sjw_max
+    }
+
+    /// Gets the minimum bitrate prescaler.
+    pub fn brp_min(&self) -> u32 {
+        self.0.brp_min
+    }
+
+    /// Gets the maximum bitrate prescaler.
+    pub fn brp_max(&self) -> u32 {
+        self.0.brp_max
+    }
+
+    /// Gets the step size of the bitrate prescaler.
+    ///
+    /// The prescaler must be a multiple of this.
+    pub fn brp_inc(&self) -> u32 {
+        self.0.brp_inc
+    }
+
+    /// Determines if the register-level bit timing is within the limits.
+    ///
+    /// This checks the segment lengths, the synchronisation jump width,
+    /// and the prescaler, so that an invalid timing can be rejected before
+    /// it is sent to the kernel.
+    pub fn contains(&self, timing: &CanBitTiming) -> bool {
+        let btc = &self.0;
+        let tseg1 = timing.prop_seg + timing.phase_seg1;
+        (btc.tseg1_min..=btc.tseg1_max).contains(&tseg1)
+            && (btc.tseg2_min..=btc.tseg2_max).contains(&timing.phase_seg2)
+            && timing.sjw <= btc.sjw_max
+            && (btc.brp_min..=btc.brp_max).contains(&timing.brp)
+            && timing.brp % btc.brp_inc.max(1) == 0
+    }
+
+    /// Gets the raw kernel bit timing constants struct.
+    pub fn into_inner(self) -> CanBitTimingConst {
+        self.0
+    }
+}
+
+/// Formats the limits the same way as `ip -details link show`, such as:
+///
+/// ```text
+/// mcp251x: tseg1 3..16 tseg2 2..8 sjw 1..4 brp 1..64 brp-inc 1
+/// ```
+///
+/// The controller name and colon are left out if the name is empty.
+impl fmt::Display for BitTimingLimits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = self.name();
+        if !name.is_empty() {
+            write!(f, "{}: ", name)?;
+        }
+        write!(
+            f,
+            "tseg1 {}..{} tseg2 {}..{} sjw 1..{} brp {}..{} brp-inc {}",
+            self.0.tseg1_min,
+            self.0.tseg1_max,
+            self.0.tseg2_min,
+            self.0.tseg2_max,
+            self.0.sjw_max,
+            self.0.brp_min,
+            self.0.brp_max,
+            self.0.brp_inc
+        )
+    }
+}
+
+impl From<CanBitTimingConst> for BitTimingLimits {
+    fn from(limits: CanBitTimingConst) -> Self {
+        Self(limits)
+    }
+}
+
+impl From<BitTimingLimits> for CanBitTimingConst {
+    fn from(limits: BitTimingLimits) -> Self {
+        limits.0
+    }
+}
+
+/// The sample point within a CAN bit.
+///
+/// The kernel specifies the sample point in tenths of a percent (per
+/// mille), which is easy to get wrong when passing a raw integer. This
+/// type makes the units explicit, like `SamplePoint::from_percent(87.5)`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct SamplePoint(u32);
+
+impl SamplePoint {
+    /// Creates a sample point from a percentage of the bit time,
+    /// like 87.5
+    pub fn from_percent(percent: f32) -> Self {
+        Self((percent * 10.0).round() as u32)
+    }
+
+    /// Creates a sample point from tenths of a percent of the bit time,
+    /// like 875, as used by the kernel.
+    pub fn from_per_mille(per_mille: u32) -> Self {
+        Self(per_mille)
+    }
+
+    /// Gets the sample point as a percentage of the bit time.
+    pub fn percent(&self) -> f32 {
+        self.0 as f32 / 10.0
+    }
+
+    /// Gets the sample point in tenths of a percent of the bit time.
+    pub fn per_mille(&self) -> u32 {
+        self.0
+    }
+}
+
+impl From<SamplePoint> for u32 {
+    fn from(sample_point: SamplePoint) -> Self {
+        sample_point.0
+    }
+}
+
+/// A builder for register-level CAN bit timing parameters.
+///
+/// This is for applications that know the exact segment layout they
+/// want, rather than a bitrate and sample point. The segment lengths and
+/// the synchronisation jump width are all given in time quanta (TQ).
+///
+/// Note that the kernel only accepts register-level timing with the time
+/// quantum set and the bitrate left at zero, and derives the prescaler
+/// from the length of the time quantum, which depends on the CAN clock of
+/// the interface. So the timing must be built with
+/// [build_for](BitTimingBuilder::build_for) or
+/// [build_with_clock](BitTimingBuilder::build_with_clock) to be sent to
+/// the kernel. The kernel then fills in the bitrate and sample point
+/// itself. Use [`BitTiming::bitrate_for_clock`] to see the bitrate ahead
+/// of time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BitTimingBuilder {
+    brp: u32,
+    prop_seg: u32,
+    phase_seg1: u32,
+    phase_seg2: u32,
+    sjw: u32,
+}
+
+impl BitTimingBuilder {
+    /// Creates a new builder with all the values set to zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the bitrate prescaler.
+    pub fn brp(mut self, brp: u32) -> Self {
+        self.brp = brp;
+        self
+    }
+
+    /// Sets the propagation segment length, in TQ.
+    pub fn prop_seg(mut self, prop_seg: u32) -> Self {
+        self.prop_seg = prop_seg;
+        self
+    }
+
+    /// Sets the phase buffer segment 1 length, in TQ.
+    pub fn phase_seg1(mut self, phase_seg1: u32) -> Self {
+        self.phase_seg1 = phase_seg1;
+        self
+    }
+
+    /// Sets the phase buffer segment 2 length, in TQ.
+    pub fn phase_seg2(mut self, phase_seg2: u32) -> Self {
+        self.phase_seg2 = phase_seg2;
+        self
+    }
+
+    /// Sets the synchronisation jump width, in TQ.
+    pub fn sjw(mut self, sjw: u32) -> Self {
+        self.sjw = sjw;
+        self
+    }
+
+    /// Builds the raw bit timing with just the register-level values.
+    ///
+    /// The bitrate, sample point, and time quantum are left at zero, so
+    /// the kernel won't accept this as-is. It is useful for checking
+    /// against the [limits](BitTimingLimits::contains) of a controller.
+    pub fn build(&self) -> CanBitTiming {
+        CanBitTiming {
+            brp: self.brp,
+            prop_seg: self.prop_seg,
+            phase_seg1: self.phase_seg1,
+            phase_seg2: self.phase_seg2,
+            sjw: self.sjw,
+            ..CanBitTiming::default()
+        }
+    }
+
+    /// Builds the bit timing to send to the kernel for a CAN clock of the
+    /// specified frequency, in Hz.
+    ///
+    /// This fills in the time quantum from the prescaler. The bitrate and
+    /// sample point are left at zero, since the kernel rejects timing
+    /// with both the time quantum and the bitrate set.
+    pub fn build_with_clock(&self, clock: u32) -> CanBitTiming {
+        let mut timing = self.build();
+        let brp = u64::from(self.brp.max(1));
+        let clock_hz = u64::from(clock.max(1));
+
+        timing.tq = ((brp * 1_000_000_000 + clock_hz / 2) / clock_hz) as u32;
+        timing
+    }
+
+    /// Builds the complete bit timing for the specified interface.
+    ///
+    /// This reads the CAN clock and the bit timing limits from the
+    /// interface, and returns an error if the values are out of the range
+    /// supported by the hardware.
+    pub fn build_for(&self, iface: &CanInterface) -> NlResult<CanBitTiming> {
+        let clock = iface.clock()?.ok_or(CanNlError::NoClock)?;
+
+        let timing = self.build_with_clock(clock);
+        if let Some(btc) = iface.bit_timing_const()? {
+            if !BitTimingLimits::from(btc).contains(&timing) {
+                return Err(CanNlError::TimingOutOfRange);
+            }
+        }
+        Ok(timing)
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// Converts a `bitrate`, in bits per second, to the bit time, in
+/// nanoseconds.
+///
+/// This is the starting point for working out the time that a frame
+/// spends on the wire. It is zero if the bitrate is zero.
+pub fn bit_time_ns(bitrate: u32) -> f64 {
+    if bitrate == 0 {
+        return 0.0;
+    }
+    1e9 / bitrate as f64
+}
+
+/// Calculates the register-level bit timing for a bitrate.
+///
+/// This finds the prescaler and segment lengths, within the limits of the
+/// controller, `btc`, that come closest to the requested `bitrate` at the
+/// CAN `clock` frequency (in Hz), much like the kernel does when only a
+/// bitrate is given. The prescaler is only ever a multiple of the
+/// `brp_inc` step of the controller. If no `sample_point` is given, the
+/// CiA recommended one for the bitrate is used.
+///
+/// The result is in the form that the kernel accepts with
+/// [set_bit_timing](super::CanInterface::set_bit_timing): the time
+/// quantum is set, and the bitrate and sample point are left at zero for
+/// the kernel to fill in. Use [`BitTiming::bitrate_for_clock`] to get the
+/// bitrate that it will run at.
+///
+/// This returns `None` if the bitrate can't be reached within 5% with
+/// the limits of the controller.
+pub fn calc_bit_timing(
+    clock: u32,
+    btc: &CanBitTimingConst,
+    bitrate: u32,
+    sample_point: Option<SamplePoint>,
+) -> Option<CanBitTiming> {
+    const MAX_ERROR_PER_MILLE: u64 = 50;
+
+    if clock == 0 || bitrate == 0 {
+        return None;
+    }
+
+    let sample_point = sample_point.map(u32::from).unwrap_or(match bitrate {
+        b if b > 800_000 => 750,
+        b if b > 500_000 => 800,
+        _ => 875,
+    });
+
+    let clock = u64::from(clock);
+    let target = u64::from(bitrate);
+    let brp_inc = u64::from(btc.brp_inc.max(1));
+    let brp_range = u64::from(btc.brp_min.max(1))..=u64::from(btc.brp_max);
+
+    // The best (error, brp, nbt), preferring longer bit times on a tie
+    let mut best: Option<(u64, u64, u64)> = None;
+    let nbt_min = u64::from(1 + btc.tseg1_min + btc.tseg2_min);
+    let nbt_max = u64::from(1 + btc.tseg1_max + btc.tseg2_max);
+
+    for nbt in (nbt_min.max(2)..=nbt_max).rev() {
+        // Try the prescaler steps on either side of the ideal value
+        let brp_lo = clock / (target * nbt) / brp_inc * brp_inc;
+        for brp in [brp_lo, brp_lo + brp_inc] {
+            if !brp_range.contains(&brp) {
+                continue;
+            }
+            let err = (clock / (brp * nbt)).abs_diff(target);
+            if best.map_or(true, |(best_err, _, _)| err < best_err) {
+                best = Some((err, brp, nbt));
+            }
+        }
+    }
+
+    let (err, brp, nbt) = best?;
+    if 1000 * err / target > MAX_ERROR_PER_MILLE {
+        return None;
+    }
+
+    // Split the bit time around the sample point
+    let nbt = nbt as u32;
+    let tseg = nbt - 1;
+    let mut tseg2 = nbt
+        .saturating_sub((sample_point * nbt + 500) / 1000)
+        .clamp(btc.tseg2_min, btc.tseg2_max);
+    let mut tseg1 = tseg.saturating_sub(tseg2);
+    if tseg1 > btc.tseg1_max {
+        tseg1 = btc.tseg1_max;
+        tseg2 = tseg - tseg1;
+    } else if tseg1 < btc.tseg1_min {
+        tseg1 = btc.tseg1_min;
+        tseg2 = tseg - tseg1;
+    }
+    let prop_seg = tseg1 / 2;
+
+    let timing = BitTimingBuilder::new()
+        .brp(brp as u32)
+        .prop_seg(prop_seg)
+        .phase_seg1(tseg1 - prop_seg)
+        .phase_seg2(tseg2)
+        .sjw(1)
+        .build_with_clock(clock as u32);
+    BitTimingLimits::from(*btc)
+        .contains(&timing)
+        .then_some(timing)
+}
+
+/// Gets the fastest bitrate that the bit timing limits allow at the clock
+/// frequency, `clock` (in Hz).
+///
+/// This uses the smallest prescaler and the shortest segments, plus the
+/// one time quantum for the sync segment. It returns `None` if the limits
+/// don't allow for a valid bit time.
+pub(super) fn max_bitrate(clock: u32, btc: &CanBitTimingConst) -> Option<u32> {
+    let brp = btc.brp_min.max(1);
+    let nbt = 1 + btc.tseg1_min.max(1) + btc.tseg2_min.max(1);
+    clock.checked_div(brp.checked_mul(nbt)?)
+}
+
+/// Gets the slowest bitrate that the bit timing limits allow at the clock
+/// frequency, `clock` (in Hz).
+///
+/// This uses the largest prescaler and the longest segments, plus the
+/// one time quantum for the sync segment. It returns `None` if the limits
+/// don't allow for a valid bit time.
+pub(super) fn min_bitrate(clock: u32, btc: &CanBitTimingConst) -> Option<u32> {
+    let brp = u64::from(btc.brp_max);
+    let nbt = 1 + u64::from(btc.tseg1_max) + u64::from(btc.tseg2_max);
+    let bitrate = u64::from(clock).checked_div(brp * nbt)?;
+    u32::try_from(bitrate.max(1)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_time_ns() {
+        assert_eq!(bit_time_ns(500_000), 2000.0);
+        assert_eq!(bit_time_ns(1_000_000), 1000.0);
+        assert_eq!(bit_time_ns(0), 0.0);
+
+        let timing = BitTiming::from(CanBitTiming {
+            bitrate: 250_000,
+            brp: 4,
+            ..CanBitTiming::default()
+        });
+        assert_eq!(timing.bit_time_ns(), 4000.0);
+        assert_eq!(timing.tq_ns(80_000_000), 50.0);
+        assert_eq!(timing.tq_ns(0), 0.0);
+    }
+
+    #[test]
+    fn test_build_with_clock() {
+        // 500kbps @ 87.5% on an 80MHz clock
+        let timing = BitTimingBuilder::new()
+            .brp(10)
+            .prop_seg(6)
+            .phase_seg1(7)
+            .phase_seg2(2)
+            .sjw(1)
+            .build_with_clock(80_000_000);
+
+        // The form that the kernel accepts
+        assert!(timing.tq != 0 && timing.bitrate == 0);
+        assert_eq!(timing.tq, 125);
+        assert_eq!(timing.sample_point, 0);
+        assert_eq!(timing.brp, 10);
+
+        let timing = BitTiming::from(timing);
+        assert_eq!(timing.bitrate_for_clock(80_000_000), 500_000);
+        assert_eq!(timing.sample_point_percent(), 87.5);
+    }
+
+    #[test]
+    fn test_max_bitrate() {
+        // Limits of a typical FD controller's data phase
+        let btc = CanBitTimingConst {
+            tseg1_min: 1,
+            tseg1_max: 32,
+            tseg2_min: 1,
+            tseg2_max: 16,
+            sjw_max: 16,
+            brp_min: 1,
+            brp_max: 32,
+            brp_inc: 1,
+            ..CanBitTimingConst::default()
+        };
+        assert_eq!(max_bitrate(80_000_000, &btc), Some(26_666_666));
+
+        let btc = CanBitTimingConst {
+            brp_min: 2,
+            tseg1_min: 4,
+            tseg2_min: 3,
+            ..btc
+        };
+        assert_eq!(max_bitrate(80_000_000, &btc), Some(5_000_000));
+    }
+
+    #[test]
+    fn test_min_bitrate() {
+        let btc = CanBitTimingConst {
+            tseg1_min: 1,
+            tseg1_max: 16,
+            tseg2_min: 1,
+            tseg2_max: 8,
+            sjw_max: 4,
+            brp_min: 1,
+            brp_max: 64,
+            brp_inc: 1,
+            ..CanBitTimingConst::default()
+        };
+        // 8MHz / (64 * 25)
+        assert_eq!(min_bitrate(8_000_000, &btc), Some(5_000));
+
+        let btc = CanBitTimingConst { brp_max: 0, ..btc };
+        assert_eq!(min_bitrate(8_000_000, &btc), None);
+    }
+
+    #[test]
+    fn test_calc_bit_timing_brp_inc() {
+        let btc = CanBitTimingConst {
+            tseg1_min: 2,
+            tseg1_max: 6,
+            tseg2_min: 1,
+            tseg2_max: 4,
+            sjw_max: 4,
+            brp_min: 1,
+            brp_max: 64,
+            brp_inc: 1,
+            ..CanBitTimingConst::default()
+        };
+
+        // With any prescaler allowed, an odd one is the best fit
+        let timing = calc_bit_timing(30_000_000, &btc, 1_000_000, None).unwrap();
+        assert!(timing.tq != 0 && timing.bitrate == 0 && timing.sample_point == 0);
+        assert_eq!(timing.brp, 3);
+        assert_eq!(
+            BitTiming::from(timing).bitrate_for_clock(30_000_000),
+            1_000_000
+        );
+
+        // But only even ones are produced when the controller needs it
+        let btc = CanBitTimingConst { brp_inc: 2, ..btc };
+        for bitrate in [125_000, 250_000, 500_000, 1_000_000] {
+            let timing = calc_bit_timing(30_000_000, &btc, bitrate, None).unwrap();
+            assert_eq!(timing.brp % 2, 0);
+            assert_eq!(
+                BitTiming::from(timing).bitrate_for_clock(30_000_000),
+                bitrate
+            );
+            let tseg1 = timing.prop_seg + timing.phase_seg1;
+            assert!((btc.tseg1_min..=btc.tseg1_max).contains(&tseg1));
+            assert!((btc.tseg2_min..=btc.tseg2_max).contains(&timing.phase_seg2));
+        }
+    }
+
+    #[test]
+    fn test_limits_contains() {
+        let limits = BitTimingLimits::from(CanBitTimingConst {
+            tseg1_min: 2,
+            tseg1_max: 16,
+            tseg2_min: 1,
+            tseg2_max: 8,
+            sjw_max: 4,
+            brp_min: 1,
+            brp_max: 64,
+            brp_inc: 2,
+            ..CanBitTimingConst::default()
+        });
+        assert_eq!(limits.tseg1_max(), 16);
+        assert_eq!(limits.brp_inc(), 2);
+        assert_eq!(limits.name(), "");
+
+        let builder = BitTimingBuilder::new()
+            .brp(10)
+            .prop_seg(6)
+            .phase_seg1(7)
+            .phase_seg2(2)
+            .sjw(1);
+        assert!(limits.contains(&builder.build()));
+
+        // Odd prescaler, with an increment of 2
+        assert!(!limits.contains(&builder.brp(9).build()));
+        // Time segment 1 is too long
+        assert!(!limits.contains(&builder.phase_seg1(11).build()));
+        // Jump width is too wide
+        assert!(!limits.contains(&builder.sjw(5).build()));
+    }
+
+    #[test]
+    fn test_limits_display() {
+        let mut btc = CanBitTimingConst {
+            tseg1_min: 1,
+            tseg1_max: 256,
+            tseg2_min: 1,
+            tseg2_max: 128,
+            sjw_max: 128,
+            brp_min: 1,
+            brp_max: 512,
+            brp_inc: 1,
+            ..CanBitTimingConst::default()
+        };
+        assert_eq!(
+            BitTimingLimits::from(btc).to_string(),
+            "tseg1 1..256 tseg2 1..128 sjw 1..128 brp 1..512 brp-inc 1"
+        );
+
+        for (c, b) in btc.name.iter_mut().zip(b"m_can") {
+            *c = *b as _;
+        }
+        assert_eq!(
+            BitTimingLimits::from(btc).to_string(),
+            "m_can: tseg1 1..256 tseg2 1..128 sjw 1..128 brp 1..512 brp-inc 1"
+        );
+    }
+
+    #[test]
+    fn test_sample_point_percent() {
+        let timing = BitTiming::from(
+            BitTimingBuilder::new()
+                .brp(10)
+                .prop_seg(6)
+                .phase_seg1(7)
+                .phase_seg2(2)
+                .build(),
+        );
+        assert_eq!(timing.sample_point_percent(), 87.5);
+
+        let timing = BitTiming::from(
+            BitTimingBuilder::new()
+                .prop_seg(5)
+                .phase_seg1(6)
+                .phase_seg2(3)
+                .build(),
+        );
+        assert!((timing.sample_point_percent() - 80.0).abs() < 0.001);
+
+        assert_eq!(BitTiming::default().sample_point_percent(), 0.0);
+    }
+
+    #[test]
+    fn test_sample_point() {
+        let sp = SamplePoint::from_percent(87.5);
+        assert_eq!(sp.per_mille(), 875);
+        assert_eq!(u32::from(sp), 875);
+        assert_eq!(sp, SamplePoint::from_per_mille(875));
+        assert_eq!(SamplePoint::from_per_mille(750).percent(), 75.0);
+    }
+}